@@ -7,7 +7,7 @@ pub struct Write {
     pub content: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum Stage {
     Build,
@@ -17,6 +17,15 @@ pub enum Stage {
     // Dependency,
 }
 
+/// Collection an action is repeated for
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Foreach {
+    /// Execute the action once per artifact produced by the build
+    /// (`$ARTIFACT`, `$ARTIFACT_NAME`, `$ARTIFACT_TARGET`)
+    Artifacts,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Action {
@@ -25,47 +34,92 @@ pub struct Action {
     pub arch: Option<Vec<Architecture>>,
     pub family: Option<PlatformFamily>,
     pub stage: Option<Stage>,
+    /// Execute only if one of the listed targets is being built
+    pub targets: Option<Vec<Target>>,
+    /// Execute only if the expression evaluates to `true`
+    /// (e.g. `env.CI == 'true' && target.contains('snap') && !sdk`)
+    #[serde(rename = "if")]
+    pub condition: Option<String>,
+    pub foreach: Option<Foreach>,
     pub items: Vec<ActionItem>,
 }
 
 impl Action {
+    /// `true` if the action is executed at `stage` on the target platform
+    /// and its `targets` and `if` conditions are met
+    pub fn is_active(
+        &self,
+        stage: &Stage,
+        ctx: &Context,
+        tpl: &Tpl,
+        targets: &TargetSet,
+    ) -> Result<bool> {
+        let active = stage == self.stage.as_ref().unwrap_or(&Stage::Build)
+            && self
+                .platform
+                .as_ref()
+                .is_none_or(|platforms| platforms.contains(&ctx.platform))
+            && self
+                .arch
+                .as_ref()
+                .is_none_or(|arch| arch.contains(&ctx.arch))
+            && self
+                .family
+                .as_ref()
+                .is_none_or(|family| family == &PlatformFamily::from(ctx.platform.clone()));
+        if !active {
+            return Ok(false);
+        }
+
+        is_selected(&self.condition, &self.targets, stage, ctx, tpl, targets)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub async fn execute(
         &self,
         stage: &Stage,
         ctx: &Context,
         tpl: &Tpl,
+        artifacts: &[Artifact],
+        targets: &TargetSet,
         src_folder: &Path,
         dest_folder: &Path,
     ) -> Result<()> {
-        if stage != self.stage.as_ref().unwrap_or(&Stage::Build) {
+        if !self.is_active(stage, ctx, tpl, targets)? {
             return Ok(());
         }
 
-        if let Some(platforms) = &self.platform {
-            if !platforms.contains(&ctx.platform) {
-                return Ok(());
-            }
-        }
-
-        if let Some(arch) = &self.arch {
-            if !arch.contains(&ctx.arch) {
-                return Ok(());
-            }
-        }
-
-        if let Some(family) = &self.family {
-            if family != &PlatformFamily::default() {
-                return Ok(());
-            }
-        }
-
         if let Some(name) = &self.name {
             log_info!("Action", "{name} ...");
         }
+        emit(&Event::Action {
+            package: &ctx.app_snake_name,
+            stage,
+            name: self.name.as_deref(),
+        });
 
-        for item in self.items.iter() {
-            item.execute(stage, ctx, tpl, src_folder, dest_folder)
-                .await?;
+        match self.foreach {
+            Some(Foreach::Artifacts) => {
+                if artifacts.is_empty() {
+                    log_warn!(
+                        "Action",
+                        "no artifacts available for `foreach = \"artifacts\"`"
+                    );
+                }
+                for artifact in artifacts.iter() {
+                    let tpl = tpl.extend(&artifact.tpl());
+                    for item in self.items.iter() {
+                        item.execute(stage, ctx, &tpl, targets, src_folder, dest_folder)
+                            .await?;
+                    }
+                }
+            }
+            None => {
+                for item in self.items.iter() {
+                    item.execute(stage, ctx, tpl, targets, src_folder, dest_folder)
+                        .await?;
+                }
+            }
         }
 
         Ok(())
@@ -79,6 +133,11 @@ pub struct ActionItem {
     pub arch: Option<Vec<Architecture>>,
     pub family: Option<PlatformFamily>,
     pub stage: Option<Stage>,
+    /// Execute only if one of the listed targets is being built
+    pub targets: Option<Vec<Target>>,
+    /// Execute only if the expression evaluates to `true`
+    #[serde(rename = "if")]
+    pub condition: Option<String>,
 
     pub run: Option<ExecutionContext>,
     pub copy: Option<Copy>,
@@ -87,39 +146,51 @@ pub struct ActionItem {
 }
 
 impl ActionItem {
+    /// `true` if the action item is executed at `stage` on the target platform
+    /// and its `targets` and `if` conditions are met
+    pub fn is_active(
+        &self,
+        stage: &Stage,
+        ctx: &Context,
+        tpl: &Tpl,
+        targets: &TargetSet,
+    ) -> Result<bool> {
+        let active = stage == self.stage.as_ref().unwrap_or(&Stage::Build)
+            && self
+                .platform
+                .as_ref()
+                .is_none_or(|platforms| platforms.contains(&ctx.platform))
+            && self
+                .arch
+                .as_ref()
+                .is_none_or(|arch| arch.contains(&ctx.arch))
+            && self
+                .family
+                .as_ref()
+                .is_none_or(|family| family == &PlatformFamily::from(ctx.platform.clone()));
+        if !active {
+            return Ok(false);
+        }
+
+        is_selected(&self.condition, &self.targets, stage, ctx, tpl, targets)
+    }
+
     pub async fn execute(
         &self,
         stage: &Stage,
         ctx: &Context,
         tpl: &Tpl,
+        targets: &TargetSet,
         src_folder: &Path,
         dest_folder: &Path,
     ) -> Result<()> {
         let src_folder = normalize(src_folder)?;
         let dest_folder = normalize(dest_folder)?;
 
-        if stage != self.stage.as_ref().unwrap_or(&Stage::Build) {
+        if !self.is_active(stage, ctx, tpl, targets)? {
             return Ok(());
         }
 
-        if let Some(platforms) = &self.platform {
-            if !platforms.contains(&ctx.platform) {
-                return Ok(());
-            }
-        }
-
-        if let Some(arch) = &self.arch {
-            if !arch.contains(&ctx.arch) {
-                return Ok(());
-            }
-        }
-
-        if let Some(family) = &self.family {
-            if family != &PlatformFamily::default() {
-                return Ok(());
-            }
-        }
-
         if let Some(execution_context) = &self.run {
             execute_with_context(ctx, execution_context, Some(&src_folder), tpl).await?;
         }
@@ -148,16 +219,41 @@ impl ActionItem {
     }
 }
 
+/// `true` if one of the `filter` targets is being built
+/// and the `condition` expression evaluates to `true`
+fn is_selected(
+    condition: &Option<String>,
+    filter: &Option<Vec<Target>>,
+    stage: &Stage,
+    ctx: &Context,
+    tpl: &Tpl,
+    targets: &TargetSet,
+) -> Result<bool> {
+    if let Some(filter) = filter {
+        if !filter.iter().any(|target| targets.contains(target)) {
+            return Ok(false);
+        }
+    }
+
+    match condition {
+        Some(condition) => Expr::parse(condition)?.eval(&Scope::new(stage, ctx, tpl, targets)),
+        None => Ok(true),
+    }
+}
+
 pub async fn execute_actions(
     stage: Stage,
     ctx: &Context,
     tpl: &Tpl,
+    artifacts: &[Artifact],
+    targets: &TargetSet,
     // src_folder: &Path,
     // dest_folder: &Path,
     // installer: &Box<dyn Installer>,
     target_folder: &Path,
 ) -> Result<()> {
     if let Some(actions) = &ctx.manifest.action {
+        let tpl = &tpl.extend(&artifacts_tpl(artifacts));
         // let actions = actions
         //     .iter()
         //     .filter(|action|
@@ -175,7 +271,15 @@ pub async fn execute_actions(
         for action in actions {
             // println!("execution action: {:?}", action);
             action
-                .execute(&stage, ctx, tpl, &ctx.project_root_folder, target_folder)
+                .execute(
+                    &stage,
+                    ctx,
+                    tpl,
+                    artifacts,
+                    targets,
+                    &ctx.project_root_folder,
+                    target_folder,
+                )
                 .await?;
         }
     }
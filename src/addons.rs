@@ -0,0 +1,166 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Default location of NW headers and Windows import libraries
+pub const NW_HEADERS_SOURCE: &str = "https://dl.nwjs.io";
+
+/// NW headers used to rebuild native Node addons. Headers are
+/// obtained from `<source>/v<version>/nw-headers-v<version>.tar.gz`,
+/// where the source is a URL or a local folder with the same layout
+/// as the NW download site, and are cached in `~/.cargo-nw`.
+#[derive(Debug)]
+pub struct NwHeaders {
+    version: String,
+    source: String,
+    dir: PathBuf,
+    download: Option<Download>,
+}
+
+impl NwHeaders {
+    pub fn new(ctx: &Context, source: Option<&str>) -> NwHeaders {
+        NwHeaders {
+            version: ctx.deps.release.version.clone(),
+            source: source
+                .unwrap_or(NW_HEADERS_SOURCE)
+                .trim_end_matches('/')
+                .to_string(),
+            dir: ctx.deps.dir.clone(),
+            download: ctx.manifest.download.clone(),
+        }
+    }
+
+    pub fn folder(&self) -> PathBuf {
+        self.dir.join(format!("nw-headers-v{}", self.version))
+    }
+
+    /// Headers folder passed to `nw-gyp` as `--nodedir`
+    pub fn nodedir(&self) -> PathBuf {
+        self.folder().join("node")
+    }
+
+    /// Download and extract the headers (and on Windows, the
+    /// import libraries for `arch`) unless already cached
+    pub async fn ensure(&self, platform: &Platform, arch: &Architecture) -> Result<()> {
+        let folder = self.folder();
+        let _lock = FileLock::acquire(
+            &self.dir.join(format!("nw-headers-v{}.lock", self.version)),
+            &format!("NW {} headers", self.version),
+        )
+        .await?;
+
+        if !self.nodedir().is_dir().await {
+            let name = format!("nw-headers-v{}.tar.gz", self.version);
+            let file = self.dir.join(&name);
+            if !file.is_file().await {
+                self.fetch(&name, &file).await?;
+            }
+            log_info!("Headers", "extracting `{name}`");
+            fs::create_dir_all(&folder).await?;
+            extract(&file, &folder).await?;
+            if !self.nodedir().is_dir().await {
+                return Err(format!("`{name}` does not contain the `node` headers folder").into());
+            }
+        }
+
+        if platform == &Platform::Windows {
+            let arch = arch.to_nwjs_arch();
+            let libs = self.nodedir().join(&arch);
+            for lib in ["nw.lib", "node.lib"] {
+                let file = libs.join(lib);
+                if !file.is_file().await {
+                    fs::create_dir_all(&libs).await?;
+                    let name = match arch.as_str() {
+                        "ia32" => lib.to_string(),
+                        arch => format!("{arch}/{lib}"),
+                    };
+                    self.fetch(&name, &file).await?;
+                }
+            }
+        }
+
+        Cache::touch(&folder).await?;
+
+        Ok(())
+    }
+
+    /// Copy or download `<source>/v<version>/<name>` into `file`
+    async fn fetch(&self, name: &str, file: &Path) -> Result<()> {
+        let location = format!("{}/v{}/{name}", self.source, self.version);
+        if location.starts_with("http://") || location.starts_with("https://") {
+            log_info!("Headers", "downloading `{location}`");
+            Downloader::new(self.download.as_ref())?
+                .download(&location, file)
+                .await
+        } else {
+            let src = Path::new(&location);
+            if !src.is_file().await {
+                return Err(format!("unable to locate NW headers file `{location}`").into());
+            }
+            fs::copy(src, file).await?;
+            Ok(())
+        }
+    }
+}
+
+/// Native addon (a package containing `binding.gyp`) located in `node_modules`
+#[derive(Debug, Clone)]
+pub struct NativeAddon {
+    /// Package name
+    pub name: String,
+    /// Package folder relative to the folder containing `node_modules`
+    pub path: String,
+}
+
+/// Find native addons within `folder/node_modules`, including packages
+/// installed in nested `node_modules` folders.
+pub fn find_native_addons(folder: &Path) -> Vec<NativeAddon> {
+    let mut addons = Vec::new();
+    let walker = WalkDir::new(folder.join("node_modules"))
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".bin");
+    for entry in walker.flatten() {
+        if entry.file_name() != "binding.gyp" || !entry.file_type().is_file() {
+            continue;
+        }
+        let Some(package) = entry.path().parent() else {
+            continue;
+        };
+        if !package.join("package.json").is_file() {
+            continue;
+        }
+
+        // the package folder must be `node_modules/<name>` or `node_modules/@scope/<name>`
+        let name = match package.parent() {
+            Some(parent) if parent.ends_with("node_modules") => {
+                package.file_name().unwrap().to_string_lossy().to_string()
+            }
+            Some(parent)
+                if parent.parent().map(|p| p.ends_with("node_modules")) == Some(true)
+                    && parent
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .starts_with('@') =>
+            {
+                format!(
+                    "{}/{}",
+                    parent.file_name().unwrap().to_string_lossy(),
+                    package.file_name().unwrap().to_string_lossy()
+                )
+            }
+            _ => continue,
+        };
+
+        let Ok(path) = package.strip_prefix(folder) else {
+            continue;
+        };
+        addons.push(NativeAddon {
+            name,
+            path: path.to_string_lossy().replace('\\', "/"),
+        });
+    }
+    addons.sort_by(|a, b| a.path.cmp(&b.path));
+    addons
+}
@@ -4,10 +4,103 @@ use async_std::{
     path::{Path, PathBuf},
 };
 use console::style;
+use sha2::{Digest, Sha256};
 use std::time::Instant;
 
+/// Default inputs of the `WASM` build directive (relative to the crate folder)
+const WASM_INPUTS: [&str; 4] = ["Cargo.toml", "Cargo.lock", "build.rs", "src/**"];
+/// Number of dependency log lines displayed when a dependency build fails
+const DEPENDENCY_LOG_TAIL: usize = 20;
+/// Default inputs of the `Cargo` build directive
+const CARGO_INPUTS: [&str; 3] = ["**/Cargo.toml", "Cargo.lock", "**/*.rs"];
+
+/// Build step resolved from the manifest without executing it or
+/// modifying the filesystem (see [`Builder::resolve_step`])
+pub struct Step {
+    /// Log source of the step
+    pub source: &'static str,
+    /// Message logged when the step is up to date
+    pub fresh: String,
+    /// Commands executed in order
+    pub commands: Vec<StepCommand>,
+    /// Environment of the commands
+    pub env: Option<Vec<String>>,
+    /// Folder the input and output globs are relative to
+    pub folder: PathBuf,
+    /// Source files the step depends on
+    pub inputs: Vec<String>,
+    /// Files produced by the step
+    pub outputs: Vec<String>,
+    /// The step is executed even if it is up to date (`clean` or `purge`)
+    pub force: bool,
+    key: String,
+    kind: StepKind,
+}
+
+/// Command of a build step
+pub struct StepCommand {
+    /// Log source of the command
+    pub source: &'static str,
+    /// Message logged when the command is executed
+    pub message: String,
+    pub cwd: PathBuf,
+    pub args: ExecArgs,
+}
+
+/// State used to prepare a build step and to integrate its output
+enum StepKind {
+    Wasm {
+        crate_folder: PathBuf,
+        clean: bool,
+        purge: bool,
+    },
+    Npm {
+        manager: PackageManager,
+        version: String,
+        clean: bool,
+        clean_package_lock: bool,
+        install: NpmInstall,
+    },
+    Cargo {
+        output_folder: PathBuf,
+        binaries: Vec<String>,
+        copy_to: Option<String>,
+    },
+    Rebuild {
+        headers: NwHeaders,
+        install: NpmInstall,
+    },
+    Custom,
+}
+
+impl Step {
+    /// Fingerprint of the step computed from the current contents of its inputs
+    pub fn fingerprint(&self, ctx: &Context, tpl: &Tpl) -> Result<Fingerprint> {
+        let mut command = Vec::new();
+        for step in self.commands.iter() {
+            if let Ok(cwd) = step.cwd.strip_prefix(&self.folder) {
+                command.push(cwd.to_string_lossy().replace('\\', "/"));
+            }
+            command.extend(step.args.get(tpl));
+        }
+        Fingerprint::try_new(
+            ctx,
+            &self.key,
+            &self.folder,
+            &command,
+            &self.env,
+            &self.inputs,
+            &self.outputs,
+        )
+    }
+}
+
 pub struct Builder {
     pub ctx: Arc<Context>,
+    /// Template of the package that executed the build steps that are not
+    /// target-specific (set when building additional platforms, architectures,
+    /// flavors and variants, which skip these steps)
+    pub common_tpl: Option<Tpl>,
     // #[cfg(feature = "unix")]
     // platform : Platform,
 }
@@ -17,17 +110,32 @@ impl Builder {
     pub fn new(ctx: Arc<Context>) -> Self {
         Builder {
             ctx,
+            common_tpl: None,
             // platform
         }
     }
 
+    pub fn with_common_tpl(mut self, common_tpl: Option<Tpl>) -> Self {
+        self.common_tpl = common_tpl;
+        self
+    }
+
+    /// `true` if the build step has been executed by the package that
+    /// executed the common build steps (see [`Build::is_target_specific`])
+    pub fn is_common_step(&self, build: &Build, tpl: &Tpl) -> Result<bool> {
+        match &self.common_tpl {
+            Some(common_tpl) => Ok(!build.is_target_specific(tpl, common_tpl)?),
+            None => Ok(false),
+        }
+    }
+
     #[allow(clippy::borrowed_box)]
     pub async fn execute(
         self: &Arc<Self>,
         targets: &TargetSet,
         // installer: &Box<dyn Installer>,
         installer: &Box<dyn Installer>,
-    ) -> Result<()> {
+    ) -> Result<PackageReport> {
         // println!("{:#?}", self.ctx.manifest);
         // return Ok(());
 
@@ -35,128 +143,67 @@ impl Builder {
             return Err("no build targets selected".into());
         }
 
+        let ts_total = Instant::now();
+        let package = self.ctx.app_snake_name.as_str();
+        let mut report = PackageReport::new(&self.ctx);
+        emit(&Event::BuildStarted {
+            package,
+            platform: &self.ctx.platform,
+            arch: &self.ctx.arch,
+            flavor: report.flavor.as_deref(),
+            variant: report.variant.as_deref(),
+        });
+
         installer.check(targets).await?;
 
         let tpl = installer.tpl();
 
+        let _lock = FileLock::acquire(
+            &PathBuf::from(format!("{}.lock", self.ctx.build_folder.display())),
+            &format!("`{}`", self.ctx.build_folder.display()),
+        )
+        .await?;
+
         self.ctx.clean().await?;
-        self.ctx.deps.ensure().await?;
+        self.ctx.deps.ensure(&self.ctx.lock, false).await?;
         self.ctx.ensure_folders().await?;
 
         installer.init(targets).await?;
         // let installer = create_installer(&self.ctx);
 
+        let ts_dependencies = Instant::now();
         self.process_dependencies(&tpl, installer.target_folder())
             .await?;
+        self.ctx.lock.store().await?;
+        report.durations.dependencies = ts_dependencies.elapsed().as_secs_f64();
 
         // return Ok(());
 
+        let ts_build_steps = Instant::now();
         if let Some(builds) = &self.ctx.manifest.package.build {
             log_info!("Build", "building...");
-            println!();
-
-            for build in builds.iter() {
-                match build {
-                    Build::WASM {
-                        clean,
-                        purge,
-                        name,
-                        outdir,
-                        args,
-                        dev,
-                        env,
-                    } => {
-                        // invoke cargo clean
-                        if clean.unwrap_or(false) {
-                            log_info!("WasmPack", "cargo clean");
-                            cmd!("cargo clean").dir(&self.ctx.app_root_folder).run()?;
-                        }
-
-                        // delete the entire target folder
-                        if purge.unwrap_or(false) && self.ctx.cargo_target_folder.exists().await {
-                            log_info!("WasmPack", "purging target folder");
-                            fs::remove_dir_all(&self.ctx.cargo_target_folder).await?;
-                        }
+            log_newline();
 
-                        let outdir = outdir.clone().unwrap_or_else(|| "app/wasm".to_string());
-                        let name = name.as_ref().unwrap_or(&self.ctx.manifest.application.name);
-                        let mut argv = vec!["wasm-pack", "build"];
-                        if dev.unwrap_or(false) {
-                            argv.push("--dev");
-                        }
-                        argv.extend_from_slice(&[
-                            "--target",
-                            "web",
-                            "--out-name",
-                            name.as_str(),
-                            "--out-dir",
-                            outdir.as_str(),
-                        ]);
-                        if let Some(args) = args {
-                            argv.extend(args.split(' ').collect::<Vec<_>>());
-                        }
-
-                        log_info!("WasmPack", "building WASM target");
-                        execute(
-                            &self.ctx,
-                            &argv.into(),
-                            &self.ctx.app_root_folder,
-                            env,
-                            &None,
-                            &None,
-                            &None,
-                            &tpl,
-                        )
-                        .await?;
-                    }
-                    Build::NPM {
-                        clean,
-                        clean_package_lock,
-                        args,
-                        dev,
-                        env,
-                    } => {
-                        let node_modules_folder = self.ctx.app_root_folder.join("node_modules");
-                        if clean.unwrap_or(false) && node_modules_folder.exists().await {
-                            log_info!("NPM", "removing node_modules folder");
-                            fs::remove_dir_all(&node_modules_folder).await?;
-                        }
-                        let package_lock_file = self.ctx.app_root_folder.join("package-lock.json");
-                        if clean_package_lock.unwrap_or(false) && package_lock_file.exists().await {
-                            log_info!("NPM", "removing NPM package lock");
-                            fs::remove_file(&package_lock_file).await?;
-                        }
-
-                        let mut argv = vec!["npm", "install"];
-                        if !dev.unwrap_or(false) {
-                            argv.extend_from_slice(&["--omit", "dev"]);
-                        }
-                        if let Some(args) = args {
-                            argv.extend(args.split(' ').collect::<Vec<_>>());
-                        }
-                        log_info!("NPM", "installing");
-                        // let argv = argv.iter().map(|s|s.to_string()).collect();
-                        execute(
-                            &self.ctx,
-                            &argv.into(),
-                            &self.ctx.app_root_folder,
-                            env,
-                            &None,
-                            &None,
-                            &None,
-                            &tpl,
-                        )
-                        .await?;
-                    }
-                    Build::Custom(ec) => {
-                        log_info!("Build", "executing `{}`", ec.display(&tpl));
-                        execute_with_context(&self.ctx, ec, None, &tpl).await?;
-                    }
-                }
+            let target_folder = installer.target_folder();
+            for (index, build) in builds.iter().enumerate() {
+                let ts_step = Instant::now();
+                let status = if self.is_common_step(build, &tpl)? {
+                    StepStatus::Skipped
+                } else {
+                    self.build_step(index, build, &tpl, &target_folder).await?
+                };
+                emit(&Event::BuildStep {
+                    package,
+                    index,
+                    kind: build.kind(),
+                    status,
+                    duration: ts_step.elapsed().as_secs_f64(),
+                });
             }
 
-            println!();
+            log_newline();
         }
+        report.durations.build_steps = ts_build_steps.elapsed().as_secs_f64();
 
         let ts_start = Instant::now();
         log_info!(
@@ -178,7 +225,7 @@ impl Builder {
 
         let target_folder = installer.target_folder();
         // self.execute_actions(Stage::Build, &installer).await?;
-        execute_actions(Stage::Build, &self.ctx, &tpl, &target_folder).await?;
+        execute_actions(Stage::Build, &self.ctx, &tpl, &[], targets, &target_folder).await?;
 
         // if let Some(actions) = &self.ctx.manifest.action {
         //     let actions = actions
@@ -193,43 +240,43 @@ impl Builder {
 
         // installer execution
 
-        let files = installer.create(targets).await?;
+        for target in targets.iter() {
+            emit(&Event::Target { package, target });
+        }
+        let artifacts = installer.create(targets).await?;
 
         if self.ctx.dry_run {
             log_warn!("Integration", "dry-run completed successfully");
-            return Ok(());
-        } else if files.is_empty() {
+            report.durations.package = ts_start.elapsed().as_secs_f64();
+            report.durations.total = ts_total.elapsed().as_secs_f64();
+            return Ok(report);
+        } else if artifacts.is_empty() {
             return Err(Error::Warning("build produced no output".into()));
         }
 
         let duration = ts_start.elapsed();
 
-        let files: Vec<(_, _)> = files
-            .iter()
-            .map(|f| (f.file_name().unwrap().to_owned(), f))
-            .collect();
-
         if let Some(signatures) = &self.ctx.manifest.package.signatures {
             log_info!("Build", "generating signatures (SHA)");
-            for (_, path) in files.iter() {
-                generate_signatures(path, signatures).await?;
+            for artifact in artifacts.iter() {
+                generate_signatures(&artifact.file, signatures).await?;
             }
         }
 
-        for (_file, path) in files.iter() {
-            let package_size = (std::fs::metadata(path)?.len() as f64) / 1024.0 / 1024.0;
-            let path = if let Ok(path) = path.strip_prefix(&self.ctx.root_folder) {
-                path
-            } else {
-                path
-            };
-            log_info!(
-                "Package",
-                "{} - {}",
-                style(path.to_str().unwrap()).cyan(),
-                style(format!("{package_size:.2}Mb")).cyan()
-            );
+        for artifact in artifacts.iter() {
+            let artifact = ArtifactReport::try_new(artifact)?;
+            emit(&Event::Artifact {
+                package,
+                artifact: &artifact,
+            });
+            report.artifacts.push(artifact);
         }
+        report.durations.package = duration.as_secs_f64();
+
+        log_package_files(
+            &self.ctx.root_folder,
+            artifacts.iter().map(|artifact| &artifact.file),
+        )?;
 
         log_info!(
             "Finished",
@@ -238,7 +285,15 @@ impl Builder {
         );
 
         // let target_folder = installer.target_folder();
-        execute_actions(Stage::Deploy, &self.ctx, &tpl, &target_folder).await?;
+        execute_actions(
+            Stage::Deploy,
+            &self.ctx,
+            &tpl,
+            &artifacts,
+            targets,
+            &target_folder,
+        )
+        .await?;
 
         // self.execute_actions(Stage::Deploy, &installer).await?;
         // self.execute_actions(Stage::Deploy, &target_folder,&target_folder).await?;
@@ -253,38 +308,910 @@ impl Builder {
         //     }
         // }
 
-        println!();
+        log_newline();
 
-        Ok(())
+        report.durations.total = ts_total.elapsed().as_secs_f64();
+        Ok(report)
+    }
+
+    /// Execute a build step unless its fingerprint shows it is up to date
+    pub async fn build_step(
+        &self,
+        index: usize,
+        build: &Build,
+        tpl: &Tpl,
+        target_folder: &Path,
+    ) -> Result<StepStatus> {
+        let Some(step) = self.resolve_step(index, build, tpl).await? else {
+            if let Build::Rebuild { .. } = build {
+                log_info!("Rebuild", "no native addons found");
+            }
+            return Ok(StepStatus::Skipped);
+        };
+
+        match &step.kind {
+            StepKind::Wasm {
+                crate_folder,
+                clean,
+                purge,
+            } => {
+                // invoke cargo clean
+                if *clean {
+                    log_info!("WasmPack", "cargo clean");
+                    cmd!("cargo", "clean")
+                        .dir(crate_folder)
+                        .stdout_to_log()
+                        .run()?;
+                }
+
+                // delete the entire target folder
+                if *purge && self.ctx.cargo_target_folder.exists().await {
+                    log_info!("WasmPack", "purging target folder");
+                    fs::remove_dir_all(&self.ctx.cargo_target_folder).await?;
+                }
+            }
+            StepKind::Npm {
+                manager,
+                version,
+                clean,
+                clean_package_lock,
+                install,
+            } => {
+                log_info!("NPM", "using {manager} {version}");
+                let folder = &self.ctx.app_root_folder;
+                let node_modules_folder = folder.join("node_modules");
+                if *clean && node_modules_folder.exists().await {
+                    log_info!("NPM", "removing node_modules folder");
+                    fs::remove_dir_all(&node_modules_folder).await?;
+                }
+                if *clean_package_lock {
+                    if let Some(lockfile) = manager.find_lockfile(folder).await {
+                        log_info!("NPM", "removing {manager} package lock");
+                        fs::remove_file(&lockfile).await?;
+                    }
+                }
+                if *install == NpmInstall::Package {
+                    manager.stage(folder, &step.folder).await?;
+                }
+            }
+            StepKind::Rebuild { headers, .. } => {
+                headers.ensure(&self.ctx.platform, &self.ctx.arch).await?;
+            }
+            StepKind::Cargo { .. } | StepKind::Custom => {}
+        }
+
+        let fingerprint = step.fingerprint(&self.ctx, tpl)?;
+        let status = if !step.force && fingerprint.is_fresh().await? {
+            log_info!(step.source, "{}", step.fresh);
+            StepStatus::Fresh
+        } else {
+            for command in step.commands.iter() {
+                log_info!(command.source, "{}", command.message);
+                execute(
+                    &self.ctx,
+                    &command.args,
+                    &command.cwd,
+                    &step.env,
+                    &None,
+                    &None,
+                    &None,
+                    tpl,
+                )
+                .await?;
+            }
+            fingerprint.store().await?;
+            StepStatus::Executed
+        };
+
+        match &step.kind {
+            StepKind::Npm {
+                install: NpmInstall::Package,
+                ..
+            }
+            | StepKind::Rebuild {
+                install: NpmInstall::Package,
+                ..
+            } => {
+                log_info!(step.source, "integrating node_modules");
+                merge_node_modules(&step.folder, target_folder).await?;
+            }
+            StepKind::Cargo {
+                output_folder,
+                binaries,
+                copy_to,
+            } => {
+                let copy_to = match copy_to {
+                    Some(copy_to) => target_folder.join(copy_to),
+                    None => target_folder.to_path_buf(),
+                };
+                fs::create_dir_all(&copy_to).await?;
+                for bin in binaries.iter() {
+                    let file = executable_file_name(bin, &self.ctx.platform);
+                    log_info!("Cargo", "integrating `{file}`");
+                    copy_executable(&output_folder.join(&file), &copy_to.join(&file)).await?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(status)
+    }
+
+    /// Resolve the commands, environment, inputs and outputs of a build
+    /// step, as executed by [`Builder::build_step`], displayed by `--plan`
+    /// and watched by `--watch`. `None` if the step has nothing to execute
+    /// (no native addons or a command restricted to another platform).
+    pub async fn resolve_step(
+        &self,
+        index: usize,
+        build: &Build,
+        tpl: &Tpl,
+    ) -> Result<Option<Step>> {
+        let app_root_folder = &self.ctx.app_root_folder;
+        let step = match build {
+            Build::WASM {
+                clean,
+                purge,
+                path,
+                target,
+                profile,
+                dev,
+                name,
+                outdir,
+                wasm_opt,
+                args,
+                env,
+                inputs,
+                outputs,
+            } => {
+                let crate_folder = match path {
+                    Some(path) => app_root_folder.join(tpl.transform(path)),
+                    None => app_root_folder.clone(),
+                };
+                let outdir = outdir.clone().unwrap_or_else(|| "app/wasm".to_string());
+                // crates located in a sub-folder default to the crate name
+                let name = match (name, path) {
+                    (Some(name), _) => name.clone(),
+                    (None, Some(_)) => wasm_crate_name(&crate_folder, self.ctx.offline).await?,
+                    (None, None) => self.ctx.manifest.application.name.clone(),
+                };
+                let profile = match profile {
+                    Some(profile) => *profile,
+                    None if dev.unwrap_or(false) => WasmProfile::Dev,
+                    None => WasmProfile::Release,
+                };
+
+                let mut argv = vec!["wasm-pack".to_string(), "build".to_string()];
+                argv.extend(path.clone());
+                argv.extend(profile.flag().map(String::from));
+                argv.extend([
+                    "--target".to_string(),
+                    target.unwrap_or_default().to_string(),
+                    "--out-name".to_string(),
+                    name.clone(),
+                    "--out-dir".to_string(),
+                    app_root_folder.join(&outdir).to_string_lossy().to_string(),
+                ]);
+                if let Some(args) = args {
+                    argv.extend(args.to_vec());
+                }
+                let mut commands = vec![StepCommand {
+                    source: "WasmPack",
+                    message: format!("building `{name}` ({profile})"),
+                    cwd: app_root_folder.clone(),
+                    args: ExecArgs::Argv(argv),
+                }];
+
+                if let Some(level) = wasm_opt {
+                    let wasm_file = app_root_folder
+                        .join(&outdir)
+                        .join(format!("{name}_bg.wasm"));
+                    commands.push(StepCommand {
+                        source: "WasmOpt",
+                        message: format!("optimizing `{}`", wasm_file.display()),
+                        cwd: app_root_folder.clone(),
+                        args: ExecArgs::Argv(wasm_opt_args(level, &wasm_file)?),
+                    });
+                }
+
+                let prefix = match path {
+                    Some(path) => format!("{}/", tpl.transform(path).trim_end_matches('/')),
+                    None => String::new(),
+                };
+                let clean = clean.unwrap_or(false);
+                let purge = purge.unwrap_or(false);
+                Step {
+                    source: "WasmPack",
+                    fresh: format!("`{name}` is up to date"),
+                    commands,
+                    env: env.clone(),
+                    folder: app_root_folder.clone(),
+                    inputs: inputs.clone().unwrap_or_else(|| {
+                        WASM_INPUTS.map(|file| format!("{prefix}{file}")).to_vec()
+                    }),
+                    outputs: outputs
+                        .clone()
+                        .unwrap_or_else(|| vec![format!("{outdir}/{name}*")]),
+                    force: clean || purge,
+                    key: format!("{index}-wasm"),
+                    kind: StepKind::Wasm {
+                        crate_folder,
+                        clean,
+                        purge,
+                    },
+                }
+            }
+            Build::NPM {
+                manager,
+                clean,
+                clean_package_lock,
+                args,
+                dev,
+                frozen,
+                workspaces,
+                install,
+                env,
+                inputs,
+                outputs,
+            } => {
+                let manager = match manager {
+                    Some(manager) => *manager,
+                    None => PackageManager::detect(app_root_folder).await,
+                };
+                let version = if self.ctx.offline {
+                    manager.declared_version(app_root_folder).await
+                } else {
+                    manager.version(app_root_folder)?
+                };
+
+                let install = install.unwrap_or_default();
+                let install_folder = match install {
+                    NpmInstall::Source => app_root_folder.clone(),
+                    NpmInstall::Package => {
+                        if workspaces.is_some() {
+                            return Err(
+                                "NPM `workspaces` can not be used with `install = \"package\"`"
+                                    .into(),
+                            );
+                        }
+                        self.ctx.staging_folder.join("npm")
+                    }
+                };
+
+                // the staging folder receives the lock file of the application
+                // folder when the step executes (see `PackageManager::stage`)
+                let clean_package_lock = clean_package_lock.unwrap_or(false);
+                let has_lockfile =
+                    !clean_package_lock && manager.find_lockfile(app_root_folder).await.is_some();
+                let options = InstallOptions {
+                    dev: dev.unwrap_or(false),
+                    frozen: self.ctx.lock.locked || frozen.unwrap_or(has_lockfile),
+                    workspaces: workspaces.clone().unwrap_or_default(),
+                };
+                let mut argv = manager.install_args(&version, &options)?;
+                if let Some(args) = args {
+                    argv.extend(args.split(' ').map(String::from));
+                }
+
+                let clean = clean.unwrap_or(false);
+                Step {
+                    source: "NPM",
+                    fresh: "packages are up to date".to_string(),
+                    commands: vec![StepCommand {
+                        source: "NPM",
+                        message: "installing".to_string(),
+                        cwd: install_folder.clone(),
+                        args: ExecArgs::Argv(argv),
+                    }],
+                    env: npm_env(env, manager.install_env(&version, &options)),
+                    folder: install_folder,
+                    inputs: inputs.clone().unwrap_or_else(|| manager.inputs()),
+                    outputs: outputs
+                        .clone()
+                        .unwrap_or_else(|| vec!["node_modules/.*".to_string()]),
+                    force: clean,
+                    key: format!("{index}-npm"),
+                    kind: StepKind::Npm {
+                        manager,
+                        version,
+                        clean,
+                        clean_package_lock,
+                        install,
+                    },
+                }
+            }
+            Build::Cargo {
+                package,
+                bin,
+                profile,
+                features,
+                target,
+                copy_to,
+                env,
+                inputs,
+                outputs,
+            } => {
+                let profile = profile.as_deref().unwrap_or("release");
+                let target = match target {
+                    Some(target) => target.clone(),
+                    None => get_rust_target_triple(&self.ctx.platform, &self.ctx.arch)?,
+                };
+                let cwd = &self.ctx.project_root_folder;
+                let metadata = if self.ctx.offline {
+                    CargoMetadata::load_offline(cwd).await?
+                } else {
+                    CargoMetadata::load(cwd)?
+                };
+                let binaries = match bin {
+                    Some(bin) => bin.clone(),
+                    None => metadata.binaries(package.as_deref(), cwd)?,
+                };
+
+                let mut argv = ["cargo", "build", "--profile", profile, "--target", &target]
+                    .map(String::from)
+                    .to_vec();
+                if let Some(package) = package {
+                    argv.extend(["--package".to_string(), package.clone()]);
+                }
+                for bin in binaries.iter() {
+                    argv.extend(["--bin".to_string(), bin.clone()]);
+                }
+                if let Some(features) = features {
+                    argv.extend(["--features".to_string(), features.join(",")]);
+                }
+
+                let profile_folder = match profile {
+                    "dev" | "test" => "debug",
+                    "bench" => "release",
+                    profile => profile,
+                };
+                let output_folder = metadata
+                    .target_directory()
+                    .join(&target)
+                    .join(profile_folder);
+
+                // executables are declared as outputs only if they
+                // are located within the project folder
+                let default_outputs = || {
+                    binaries
+                        .iter()
+                        .filter_map(|bin| {
+                            let file =
+                                output_folder.join(executable_file_name(bin, &self.ctx.platform));
+                            file.strip_prefix(cwd)
+                                .ok()
+                                .map(|file| file.to_string_lossy().replace('\\', "/"))
+                        })
+                        .collect()
+                };
+                Step {
+                    source: "Cargo",
+                    fresh: format!("`{}` is up to date", binaries.join("`, `")),
+                    commands: vec![StepCommand {
+                        source: "Cargo",
+                        message: format!("building `{}` for `{target}`", binaries.join("`, `")),
+                        cwd: cwd.clone(),
+                        args: ExecArgs::Argv(argv),
+                    }],
+                    env: env.clone(),
+                    folder: cwd.clone(),
+                    inputs: inputs
+                        .clone()
+                        .unwrap_or_else(|| CARGO_INPUTS.map(String::from).to_vec()),
+                    outputs: outputs.clone().unwrap_or_else(default_outputs),
+                    force: false,
+                    key: format!("{index}-cargo"),
+                    kind: StepKind::Cargo {
+                        output_folder,
+                        binaries,
+                        copy_to: copy_to.as_ref().map(|copy_to| tpl.transform(copy_to)),
+                    },
+                }
+            }
+            Build::Rebuild {
+                modules,
+                install,
+                headers,
+                tool,
+                args,
+                env,
+                inputs,
+                outputs,
+            } => {
+                let install = install.unwrap_or_default();
+                let folder = match install {
+                    NpmInstall::Source => app_root_folder.clone(),
+                    NpmInstall::Package => self.ctx.staging_folder.join("npm"),
+                };
+                // packages are staged by a preceding `NPM` step
+                if !folder.is_dir().await {
+                    return Ok(None);
+                }
+
+                let mut addons = find_native_addons(&folder);
+                if let Some(modules) = modules {
+                    for module in modules.iter() {
+                        if !addons.iter().any(|addon| &addon.name == module) {
+                            return Err(format!("native addon `{module}` not found").into());
+                        }
+                    }
+                    addons.retain(|addon| modules.contains(&addon.name));
+                }
+                if addons.is_empty() {
+                    return Ok(None);
+                }
+
+                let headers = NwHeaders::new(&self.ctx, headers.as_deref());
+                let version = &self.ctx.deps.release.version;
+                let mut argv = tool
+                    .as_deref()
+                    .unwrap_or("nw-gyp")
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect::<Vec<_>>();
+                argv.extend([
+                    "rebuild".to_string(),
+                    format!("--target={version}"),
+                    "--runtime=node-webkit".to_string(),
+                    format!("--arch={}", self.ctx.arch.to_nwjs_arch()),
+                    format!("--nodedir={}", headers.nodedir().display()),
+                ]);
+                if let Some(args) = args {
+                    argv.extend(args.to_vec());
+                }
+
+                Step {
+                    source: "Rebuild",
+                    fresh: "native addons are up to date".to_string(),
+                    commands: addons
+                        .iter()
+                        .map(|addon| StepCommand {
+                            source: "Rebuild",
+                            message: format!("`{}` for NW {version}", addon.name),
+                            cwd: folder.join(&addon.path),
+                            args: ExecArgs::Argv(argv.clone()),
+                        })
+                        .collect(),
+                    env: env.clone(),
+                    inputs: inputs.clone().unwrap_or_else(|| {
+                        addons
+                            .iter()
+                            .flat_map(|addon| {
+                                [
+                                    format!("{}/package.json", addon.path),
+                                    format!("{}/binding.gyp", addon.path),
+                                ]
+                            })
+                            .collect()
+                    }),
+                    outputs: outputs.clone().unwrap_or_else(|| {
+                        addons
+                            .iter()
+                            .map(|addon| format!("{}/build/Release/*.node", addon.path))
+                            .collect()
+                    }),
+                    folder,
+                    force: false,
+                    key: format!("{index}-rebuild"),
+                    kind: StepKind::Rebuild { headers, install },
+                }
+            }
+            Build::Custom(ec) => {
+                if !is_active(&self.ctx, &ec.family, &ec.platform, &ec.arch) {
+                    return Ok(None);
+                }
+
+                Step {
+                    source: "Build",
+                    fresh: format!("`{}` is up to date", ec.display(tpl)),
+                    commands: vec![StepCommand {
+                        source: "Build",
+                        message: format!("executing `{}`", ec.display(tpl)),
+                        cwd: resolve_cwd(&self.ctx, ec, None, tpl)?,
+                        args: ec.get_args()?,
+                    }],
+                    env: ec.env.clone(),
+                    folder: app_root_folder.clone(),
+                    inputs: ec.inputs.clone().unwrap_or_default(),
+                    outputs: ec.outputs.clone().unwrap_or_default(),
+                    force: false,
+                    key: format!("{index}-custom"),
+                    kind: StepKind::Custom,
+                }
+            }
+        };
+
+        Ok(Some(step))
+    }
+
+    /// Re-resolve NW version and git dependency revisions and update `nw.lock`
+    pub async fn update_lock(&self) -> Result<()> {
+        self.ctx.deps.ensure(&self.ctx.lock, true).await?;
+
+        if let Some(deps) = &self.ctx.manifest.dependencies {
+            for dep in deps.iter() {
+                if let Some(git) = &dep.git {
+                    let name = dep
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| git.repository().to_string());
+                    let rev = if let Some(rev) = &git.rev {
+                        rev.clone()
+                    } else {
+                        let reference = match (&git.tag, &git.branch) {
+                            (Some(tag), _) => format!("refs/tags/{tag}"),
+                            (None, Some(branch)) => branch.clone(),
+                            (None, None) => "HEAD".to_string(),
+                        };
+                        // annotated tags are resolved to the tagged commit (`^{}`)
+                        let peeled = format!("{reference}^{{}}");
+                        let refs = cmd(
+                            "git",
+                            [
+                                "ls-remote",
+                                git.url.as_str(),
+                                reference.as_str(),
+                                peeled.as_str(),
+                            ],
+                        )
+                        .read()?;
+                        let line = refs
+                            .lines()
+                            .find(|line| line.ends_with("^{}"))
+                            .or_else(|| refs.lines().next());
+                        line.and_then(|line| line.split_whitespace().next())
+                            .ok_or_else(|| {
+                                Error::String(format!(
+                                    "unable to resolve `{reference}` in `{}`",
+                                    git.url
+                                ))
+                            })?
+                            .to_string()
+                    };
+                    log_info!("Git", "`{}` at {}", name, rev);
+                    self.ctx.lock.set_dependency_rev(&name, git, &rev)?;
+                }
+            }
+        }
+
+        self.ctx.lock.store().await
     }
 
     async fn process_dependencies(&self, tpl: &Tpl, target_folder: PathBuf) -> Result<()> {
         if let Some(deps) = &self.ctx.manifest.dependencies {
             fs::create_dir_all(&self.ctx.dependencies_folder).await?;
             for dep in deps.iter() {
-                self.process_dependency(dep, tpl, &target_folder).await?;
+                self.process_dependency(dep, tpl, &target_folder, false)
+                    .await?;
             }
         }
 
         Ok(())
     }
 
+    fn emit_dependency(&self, name: &str, status: StepStatus) {
+        emit(&Event::Dependency {
+            package: &self.ctx.app_snake_name,
+            name,
+            status,
+        });
+    }
+
+    /// Clone the dependency repository or update an existing clone. Pinned
+    /// (`rev`, `tag`) and locked revisions are checked out detached,
+    /// otherwise the branch is pulled.
+    async fn checkout_dependency(&self, name: &str, git: &Git, repo_folder: &Path) -> Result<()> {
+        let depth = git.depth.map(|depth| depth.to_string());
+        let submodules = git.submodules.unwrap_or(false);
+
+        let cloned = if repo_folder.is_dir().await {
+            false
+        } else {
+            let mut args = vec!["clone"];
+            if let Some(depth) = &depth {
+                args.extend(["--depth", depth.as_str()]);
+            }
+            if let Some(reference) = git.branch.as_ref().or(git.tag.as_ref()) {
+                log_info!("Git", "cloning `{name}` ({reference})");
+                args.extend(["-b", reference.as_str()]);
+            } else {
+                log_info!("Git", "cloning `{name}`");
+            }
+            if submodules {
+                args.push("--recurse-submodules");
+                if depth.is_some() {
+                    args.push("--shallow-submodules");
+                }
+            }
+            args.push(git.url.as_str());
+            cmd("git", args)
+                .dir(&self.ctx.dependencies_folder)
+                .stdout_to_log()
+                .run()?;
+            true
+        };
+
+        let pinned = match (&git.rev, &git.tag) {
+            (Some(rev), _) => Some(rev.clone()),
+            (None, Some(tag)) => Some(format!("refs/tags/{tag}")),
+            (None, None) if self.ctx.update => None,
+            (None, None) => self.ctx.lock.dependency_rev(git),
+        };
+
+        if let Some(rev) = &pinned {
+            log_info!("Git", "checking out `{name}` at {rev}");
+            let commit = format!("{rev}^{{commit}}");
+            let exists = cmd("git", ["cat-file", "-e", commit.as_str()])
+                .dir(repo_folder)
+                .stderr_null()
+                .run()
+                .is_ok();
+            if !exists {
+                let mut args = vec!["fetch"];
+                if let Some(depth) = &depth {
+                    args.extend(["--depth", depth.as_str()]);
+                }
+                args.push("origin");
+                match &git.tag {
+                    Some(tag) if git.rev.is_none() => args.extend(["tag", tag.as_str()]),
+                    // shallow clones do not contain the history, fetch the commit itself
+                    _ if depth.is_some() => args.push(rev.as_str()),
+                    _ => {}
+                }
+                cmd("git", args).dir(repo_folder).stdout_to_log().run()?;
+            }
+            cmd("git", ["checkout", "-q", "--detach", rev.as_str()])
+                .dir(repo_folder)
+                .stdout_to_log()
+                .run()?;
+        } else if !cloned {
+            // the repository may be left detached at a previously locked commit
+            let branch = if let Some(branch) = &git.branch {
+                branch.clone()
+            } else {
+                cmd("git", ["rev-parse", "--abbrev-ref", "origin/HEAD"])
+                    .dir(repo_folder)
+                    .read()?
+                    .trim()
+                    .trim_start_matches("origin/")
+                    .to_string()
+            };
+            log_info!("Git", "pulling `{name}`");
+            cmd("git", ["checkout", "-q", branch.as_str()])
+                .dir(repo_folder)
+                .stdout_to_log()
+                .run()?;
+            cmd("git", ["pull"])
+                .dir(repo_folder)
+                .stdout_to_log()
+                .run()?;
+        }
+
+        if submodules {
+            let mut args = vec!["submodule", "update", "--init", "--recursive"];
+            if let Some(depth) = &depth {
+                args.extend(["--depth", depth.as_str()]);
+            }
+            cmd("git", args).dir(repo_folder).stdout_to_log().run()?;
+        }
+
+        Ok(())
+    }
+
+    /// Download (or copy) the prebuilt archive of a `url` dependency,
+    /// verify its checksum and extract it. Archives are cached in the
+    /// dependencies folder. Returns the extracted folder and the
+    /// SHA-256 checksum of the archive.
+    async fn fetch_prebuilt(
+        &self,
+        name: &str,
+        url: &str,
+        dep: &Dependency,
+        tpl: &Tpl,
+    ) -> Result<(PathBuf, String)> {
+        let url = prebuilt_url(url, tpl);
+        if url.contains('$') {
+            return Err(format!("unresolved variable in dependency url `{url}`").into());
+        }
+        let (file_name, stem) = prebuilt_file_name(&url);
+
+        let archive = self.prebuilt_archive(&url);
+        if let Some(downloads) = archive.parent() {
+            fs::create_dir_all(downloads).await?;
+        }
+
+        let expected = dep
+            .sha256
+            .as_ref()
+            .map(|checksum| {
+                checksum
+                    .get(&self.ctx.platform, &self.ctx.arch)
+                    .map(|checksum| checksum.to_lowercase())
+                    .ok_or_else(|| {
+                        Error::String(format!(
+                            "dependency `{name}` has no `sha256` checksum for `{}-{}`",
+                            self.ctx.platform, self.ctx.arch
+                        ))
+                    })
+            })
+            .transpose()?;
+
+        // a cached archive failing verification (e.g. a partial download)
+        // is downloaded again once before the dependency fails
+        let mut downloaded = false;
+        let checksum = loop {
+            if !archive.is_file().await {
+                downloaded = true;
+                if url.starts_with("http://") || url.starts_with("https://") {
+                    log_info!("Dependency", "downloading `{url}`");
+                    Downloader::new(self.ctx.manifest.download.as_ref())?
+                        .download(&url, &archive)
+                        .await?;
+                } else {
+                    let src = self
+                        .ctx
+                        .project_root_folder
+                        .join(url.trim_start_matches("file://"));
+                    if !src.is_file().await {
+                        return Err(format!("unable to locate dependency file `{url}`").into());
+                    }
+                    fs::copy(&src, &archive).await?;
+                }
+            }
+
+            let checksum = sha256sum(&archive)?;
+            match &expected {
+                Some(expected) if expected != &checksum => {
+                    fs::remove_file(&archive).await?;
+                    if downloaded {
+                        return Err(format!(
+                            "checksum mismatch for `{url}`: expected {expected}, got {checksum}"
+                        )
+                        .into());
+                    }
+                    log_warn!(
+                        "Dependency",
+                        "cached `{file_name}` does not match its checksum, downloading again"
+                    );
+                }
+                Some(_) => break checksum,
+                None => {
+                    log_warn!(
+                        "Dependency",
+                        "no `sha256` specified for `{name}` ({checksum})"
+                    );
+                    break checksum;
+                }
+            }
+        };
+
+        let folder = self.ctx.dependencies_folder.join(name);
+        let marker = folder.join(".sha256");
+        if fs::read_to_string(&marker).await.ok().as_ref() != Some(&checksum) {
+            log_info!("Dependency", "extracting `{file_name}`");
+            if folder.exists().await {
+                fs::remove_dir_all(&folder).await?;
+            }
+            fs::create_dir_all(&folder).await?;
+            if stem == file_name {
+                // not an archive
+                fs::copy(&archive, folder.join(&file_name)).await?;
+            } else {
+                extract(&archive, &folder).await?;
+            }
+            fs::write(&marker, &checksum).await?;
+        }
+
+        Ok((folder, checksum))
+    }
+
+    /// Status file of a dependency and the status recorded after a successful
+    /// build; dependencies are rebuilt when the checked out revision
+    /// or the build commands change
+    pub fn dependency_status(
+        &self,
+        dep: &Dependency,
+        name: &str,
+        rev: &str,
+        tpl: &Tpl,
+    ) -> Result<(PathBuf, String)> {
+        let status_file = self.ctx.dependencies_folder.join(format!("{name}.status"));
+        let mut hasher = Sha256::new();
+        for ec in dep.run.iter() {
+            for arg in ec.get_args()?.get(tpl) {
+                hasher.update(arg.as_bytes());
+                hasher.update(b"\0");
+            }
+            for value in [&ec.cwd, &ec.name].into_iter().flatten() {
+                hasher.update(value.as_bytes());
+            }
+            for def in ec.env.iter().flatten() {
+                hasher.update(def.as_bytes());
+            }
+            hasher.update(b"\n");
+        }
+        Ok((status_file, format!("{rev}\n{:x}\n", hasher.finalize())))
+    }
+
+    /// Download location of a prebuilt dependency archive
+    pub fn prebuilt_archive(&self, url: &str) -> PathBuf {
+        let (file_name, _) = prebuilt_file_name(url);
+        let url_hash = format!("{:x}", Sha256::digest(url.as_bytes()));
+        self.ctx
+            .dependencies_folder
+            .join("downloads")
+            .join(format!("{}-{file_name}", &url_hash[..12]))
+    }
+
+    /// Rebuild the dependency `name` regardless of its build status
+    pub async fn rebuild_dependency(
+        &self,
+        name: &str,
+        tpl: &Tpl,
+        target_folder: &Path,
+    ) -> Result<()> {
+        let deps = self.ctx.manifest.dependencies.iter().flatten();
+        let names = deps
+            .clone()
+            .map(|dep| self.dependency_name(dep, tpl))
+            .collect::<Vec<_>>();
+        let Some(dep) = deps
+            .zip(names.iter())
+            .find(|(_, n)| *n == name)
+            .map(|(dep, _)| dep)
+        else {
+            return Err(format!(
+                "dependency `{name}` not found (available: {})",
+                names.join(", ")
+            )
+            .into());
+        };
+
+        self.ctx.ensure_folders().await?;
+        fs::create_dir_all(&self.ctx.dependencies_folder).await?;
+        self.process_dependency(dep, tpl, target_folder, true)
+            .await?;
+        self.ctx.lock.store().await
+    }
+
+    /// Name of the dependency, derived from the repository, folder
+    /// or archive name if not specified in the manifest
+    pub fn dependency_name(&self, dep: &Dependency, tpl: &Tpl) -> String {
+        if let Some(name) = &dep.name {
+            name.clone()
+        } else if let Some(git) = &dep.git {
+            git.repository().to_string()
+        } else if let Some(path) = &dep.path {
+            let path = tpl.transform(path);
+            Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or(path)
+        } else if let Some(url) = &dep.url {
+            prebuilt_file_name(&prebuilt_url(url, tpl)).1
+        } else {
+            "dependency".to_string()
+        }
+    }
+
     async fn process_dependency(
         &self,
         dep: &Dependency,
         tpl: &Tpl,
         target_folder: &Path,
+        force: bool,
     ) -> Result<()> {
-        let mut name = dep.name.clone(); //.map_or("dependency".to_string(),|n|n);
+        let name = self.dependency_name(dep, tpl);
 
         if let Some(platform) = &dep.platform {
             if !platform.contains(&self.ctx.platform) {
                 log_info!(
                     "Dependency",
                     "skipping `{}` on platform `{}`",
-                    name.unwrap_or_else(|| "dependency".to_string()),
+                    name,
                     self.ctx.platform.to_string()
                 );
+                self.emit_dependency(&name, StepStatus::Skipped);
                 return Ok(());
             }
         }
@@ -294,53 +1221,101 @@ impl Builder {
                 log_info!(
                     "Dependency",
                     "skipping `{}` on arch `{}`",
-                    name.unwrap_or_else(|| "dependency".to_string()),
+                    name,
                     self.ctx.arch.to_string()
                 );
+                self.emit_dependency(&name, StepStatus::Skipped);
                 return Ok(());
             }
         }
 
-        let (rebuild, dep_build_folder, status) = if let Some(git) = &dep.git {
-            let repo = Path::new(&git.url).file_name().unwrap().to_str().unwrap();
-            let repo_folder = self.ctx.dependencies_folder.join(repo);
-            let status_file = self.ctx.dependencies_folder.join(format!("{repo}.status"));
-            name = name.or_else(|| Some(repo.to_string()));
+        let (dep_build_folder, rev) = if let Some(git) = &dep.git {
+            let repo_folder = self.ctx.dependencies_folder.join(git.repository());
+            self.checkout_dependency(&name, git, &repo_folder).await?;
 
-            if repo_folder.is_dir().await {
-                log_info!("Git", "pulling `{}`", name.as_ref().unwrap());
-                cmd("git", ["pull"]).dir(&repo_folder).run()?;
-            } else {
-                let args = if let Some(branch) = &git.branch {
-                    log_info!("Git", "cloning `{}` ({})", name.as_ref().unwrap(), branch);
-                    vec!["clone", "-b", branch.as_str(), &git.url]
-                } else {
-                    log_info!("Git", "cloning `{}`", name.as_ref().unwrap());
-                    vec!["clone", &git.url]
-                };
-                cmd("git", args).dir(&self.ctx.dependencies_folder).run()?;
+            let rev = cmd("git", ["rev-parse", "HEAD"]).dir(&repo_folder).read()?;
+            self.ctx.lock.set_dependency_rev(&name, git, rev.trim())?;
+            (repo_folder, Some(rev.trim().to_string()))
+        } else if let Some(path) = &dep.path {
+            let folder = self.ctx.project_root_folder.join(tpl.transform(path));
+            if !folder.is_dir().await {
+                return Err(format!("dependency folder `{}` not found", folder.display()).into());
             }
+            let rev = path_dependency_rev(&folder);
+            (folder, rev)
+        } else if let Some(url) = &dep.url {
+            let (folder, checksum) = self.fetch_prebuilt(&name, url, dep, tpl).await?;
+            (folder, Some(checksum))
+        } else {
+            (self.ctx.dependencies_folder.clone(), None)
+        };
 
-            let status_data = cmd("git", ["show", "--summary"]).dir(&repo_folder).read()?;
-            if status_file.exists().await {
-                let last_status_data = fs::read_to_string(&status_file).await?;
-                let rebuild = status_data != last_status_data;
-                (rebuild, repo_folder, Some((status_file, status_data)))
-            } else {
-                (true, repo_folder, Some((status_file, status_data)))
+        let status = rev
+            .map(|rev| self.dependency_status(dep, &name, &rev, tpl))
+            .transpose()?;
+        let rebuild = match &status {
+            _ if force => true,
+            Some((status_file, status_data)) => {
+                fs::read_to_string(status_file).await.ok().as_ref() != Some(status_data)
             }
-        } else {
-            (true, self.ctx.dependencies_folder.clone(), None)
+            None => true,
         };
 
-        let name = name.unwrap_or_else(|| "...".into());
         if rebuild {
-            log_info!("Dependency", "building `{}`", name);
-            for ec in dep.run.iter() {
-                execute_with_context(&self.ctx, ec, Some(dep_build_folder.as_path()), tpl).await?;
+            // the status is recorded only after a successful build
+            if let Some((status_file, _)) = &status {
+                if status_file.exists().await {
+                    fs::remove_file(status_file).await?;
+                }
+            }
+
+            let log = self.ctx.dependencies_folder.join(format!("{name}.log"));
+            fs::write(&log, "").await?;
+            log_info!(
+                "Dependency",
+                "building `{}` (log: `{}`)",
+                name,
+                log.display()
+            );
+            self.emit_dependency(&name, StepStatus::Executed);
+            let total = dep.run.len();
+            for (index, ec) in dep.run.iter().enumerate() {
+                // the command output is written to the log file
+                log_state!(
+                    "Dependency",
+                    "`{name}` [{}/{total}] {} ...",
+                    index + 1,
+                    ec.display(tpl)
+                );
+                let result = execute_with_context_and_log(
+                    &self.ctx,
+                    ec,
+                    Some(dep_build_folder.as_path()),
+                    tpl,
+                    Some(&log),
+                )
+                .await;
+                log_state_clear();
+                if let Err(err) = result {
+                    let output = fs::read_to_string(&log).await.unwrap_or_default();
+                    let lines = output.lines().collect::<Vec<_>>();
+                    let tail = &lines[lines.len().saturating_sub(DEPENDENCY_LOG_TAIL)..];
+                    if !tail.is_empty() {
+                        log_warn!("Dependency", "`{name}` output (last {} lines):", tail.len());
+                        for line in tail {
+                            log_warn!("Dependency", "| {line}");
+                        }
+                    }
+                    return Err(format!(
+                        "dependency `{name}` failed: {err}\nfull log: `{}`",
+                        log.display()
+                    )
+                    .into());
+                }
             }
         } else {
             log_info!("Dependency", "skipping `{}` (build is up to date)", name);
+            self.emit_dependency(&name, StepStatus::Fresh);
         }
 
         // let tpl = self.ctx.tpl_clone();
@@ -356,3 +1331,78 @@ impl Builder {
         Ok(())
     }
 }
+
+/// Revision of a local dependency folder (`None` if not a git checkout);
+/// uncommitted changes of a local checkout are taken into account
+pub fn path_dependency_rev(folder: &Path) -> Option<String> {
+    cmd("git", ["rev-parse", "HEAD"])
+        .dir(folder)
+        .stderr_null()
+        .read()
+        .ok()
+        .map(|rev| {
+            let diff = cmd("git", ["status", "--porcelain"])
+                .dir(folder)
+                .read()
+                .unwrap_or_default()
+                + &cmd("git", ["diff", "HEAD"])
+                    .dir(folder)
+                    .read()
+                    .unwrap_or_default();
+            format!("{}\n{:x}", rev.trim(), Sha256::digest(diff.as_bytes()))
+        })
+}
+
+/// Resolve template and environment variables in the url of a prebuilt
+/// dependency (environment variables allow base urls to be configured per host)
+pub fn prebuilt_url(url: &str, tpl: &Tpl) -> String {
+    Tpl::new().extend(tpl).transform(url)
+}
+
+/// File name of a prebuilt dependency url and the file name without
+/// the archive extension (equal to the file name if not an archive)
+fn prebuilt_file_name(url: &str) -> (String, String) {
+    let file_name = url
+        .trim_end_matches('/')
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let stem = [".tar.gz", ".tgz", ".zip"]
+        .iter()
+        .find_map(|ext| file_name.strip_suffix(ext))
+        .unwrap_or(&file_name)
+        .to_string();
+    (file_name, stem)
+}
+
+/// Log package files (relative to `root_folder`) with their sizes
+pub fn log_package_files<'a>(
+    root_folder: &Path,
+    files: impl Iterator<Item = &'a PathBuf>,
+) -> Result<()> {
+    for path in files {
+        let package_size = (std::fs::metadata(path)?.len() as f64) / 1024.0 / 1024.0;
+        let path = if let Ok(path) = path.strip_prefix(root_folder) {
+            path
+        } else {
+            path
+        };
+        log_info!(
+            "Package",
+            "{} - {}",
+            style(path.to_str().unwrap()).cyan(),
+            style(format!("{package_size:.2}Mb")).cyan()
+        );
+    }
+
+    Ok(())
+}
+
+/// Step environment extended with variables required by the package manager
+fn npm_env(env: &Option<Vec<String>>, install_env: Vec<String>) -> Option<Vec<String>> {
+    if install_env.is_empty() {
+        return env.clone();
+    }
+    Some(env.iter().flatten().cloned().chain(install_env).collect())
+}
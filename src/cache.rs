@@ -0,0 +1,473 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Local, Utc};
+use console::style;
+use regex::Regex;
+use semver::Version;
+use walkdir::WalkDir;
+
+/// Suffix of the marker file written next to each distribution folder when
+/// it is used by a build (not inside the folder, as it is copied into packages)
+pub const LAST_USED_SUFFIX: &str = ".last-used";
+
+/// Kind of the cached distribution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheKind {
+    Nwjs,
+    Sdk,
+    Ffmpeg,
+    Headers,
+}
+
+impl std::fmt::Display for CacheKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CacheKind::Nwjs => "NW",
+            CacheKind::Sdk => "SDK",
+            CacheKind::Ffmpeg => "FFMPEG",
+            CacheKind::Headers => "Headers",
+        })
+    }
+}
+
+/// NW, NW SDK, FFMPEG distribution or NW headers (native addons)
+/// located in the `~/.cargo-nw` folder
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub kind: CacheKind,
+    pub version: String,
+    /// Distribution platform (`-` for platform-independent headers)
+    pub platform: String,
+    /// Distribution architecture (`-` for platform-independent headers)
+    pub arch: String,
+    /// Extracted distribution folder
+    pub folder: PathBuf,
+    /// Downloaded distribution archive (if present)
+    pub archive: Option<PathBuf>,
+    /// Combined size of the folder and the archive
+    pub size: u64,
+    pub last_used: DateTime<Utc>,
+}
+
+impl CacheEntry {
+    pub fn semver(&self) -> Option<Version> {
+        Version::parse(&self.version).ok()
+    }
+
+    /// Remove the folder and the archive while holding the lock used
+    /// by builds downloading or extracting the distribution
+    pub async fn remove(&self) -> Result<()> {
+        let name = self
+            .folder
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let _lock = FileLock::acquire(
+            &self.folder.with_file_name(format!("{name}.lock")),
+            &format!("`{name}`"),
+        )
+        .await?;
+
+        log_info!("Removing", "{self}");
+        // the entry may have been removed while waiting for the lock
+        if self.folder.is_dir().await {
+            fs::remove_dir_all(&self.folder).await?;
+        }
+        if let Some(archive) = &self.archive {
+            if archive.is_file().await {
+                fs::remove_file(archive).await?;
+            }
+        }
+        let marker = last_used_file(&self.folder);
+        if marker.is_file().await {
+            fs::remove_file(&marker).await?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for CacheEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            CacheKind::Headers => write!(f, "{} {}", self.kind, self.version),
+            _ => write!(
+                f,
+                "{} {} {}-{}",
+                self.kind, self.version, self.platform, self.arch
+            ),
+        }
+    }
+}
+
+/// Cache pruning criteria
+#[derive(Debug, Default)]
+pub struct PruneOptions {
+    /// Remove distributions not used for longer than this duration
+    pub older_than: Option<Duration>,
+    /// Keep only this number of most recent NW versions
+    pub keep: Option<usize>,
+    /// Report what would be removed without removing anything
+    pub dry_run: bool,
+}
+
+/// Management of NW distributions downloaded into `~/.cargo-nw`
+pub struct Cache {
+    pub dir: PathBuf,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cache {
+    pub fn new() -> Cache {
+        Cache {
+            dir: get_deps_folder(),
+        }
+    }
+
+    pub fn temp_folder(&self) -> PathBuf {
+        self.dir.join("temp")
+    }
+
+    /// Mark the distribution folder as used
+    pub async fn touch(folder: &Path) -> Result<()> {
+        if folder.is_dir().await {
+            fs::write(last_used_file(folder), Utc::now().to_rfc3339()).await?;
+            // markers written inside the folder by earlier versions end up in packages
+            let legacy = folder.join(LAST_USED_SUFFIX);
+            if legacy.is_file().await {
+                fs::remove_file(&legacy).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let nwjs = Regex::new(r"^nwjs-(sdk-)?v(.+)-(win|linux|osx)-(x64|ia32|arm64)$")?;
+        let ffmpeg = Regex::new(r"^ffmpeg-(.+)-(win|linux|osx)-(x64|ia32|arm64)$")?;
+        let headers = Regex::new(r"^nw-headers-v(.+)$")?;
+
+        let mut entries = Vec::new();
+        if !self.dir.is_dir().await {
+            return Ok(entries);
+        }
+
+        for entry in std::fs::read_dir(&self.dir)?.flatten() {
+            let folder: PathBuf = entry.path().into();
+            if !folder.is_dir().await {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            let (kind, version, platform, arch, archives) = if let Some(c) = nwjs.captures(&name) {
+                let kind = if c.get(1).is_some() {
+                    CacheKind::Sdk
+                } else {
+                    CacheKind::Nwjs
+                };
+                let archives = vec![format!("{name}.zip"), format!("{name}.tar.gz")];
+                (
+                    kind,
+                    c[2].to_string(),
+                    c[3].to_string(),
+                    c[4].to_string(),
+                    archives,
+                )
+            } else if let Some(c) = ffmpeg.captures(&name) {
+                let archives = vec![format!("{}-{}-{}.zip", &c[1], &c[2], &c[3])];
+                (
+                    CacheKind::Ffmpeg,
+                    c[1].to_string(),
+                    c[2].to_string(),
+                    c[3].to_string(),
+                    archives,
+                )
+            } else if let Some(c) = headers.captures(&name) {
+                let archives = vec![format!("{name}.tar.gz")];
+                (
+                    CacheKind::Headers,
+                    c[1].to_string(),
+                    "-".to_string(),
+                    "-".to_string(),
+                    archives,
+                )
+            } else {
+                continue;
+            };
+
+            let mut archive = None;
+            for file in archives {
+                let file = self.dir.join(file);
+                if file.is_file().await {
+                    archive = Some(file);
+                }
+            }
+
+            let size = folder_size(&folder)
+                + archive
+                    .as_ref()
+                    .and_then(|archive| std::fs::metadata(archive).ok())
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0);
+
+            let last_used = last_used(&folder).await;
+
+            entries.push(CacheEntry {
+                kind,
+                version,
+                platform,
+                arch,
+                folder,
+                archive,
+                size,
+                last_used,
+            });
+        }
+
+        entries.sort_by(|a, b| {
+            b.semver()
+                .cmp(&a.semver())
+                .then(a.platform.cmp(&b.platform))
+                .then(a.arch.cmp(&b.arch))
+                .then(a.kind.to_string().cmp(&b.kind.to_string()))
+        });
+
+        Ok(entries)
+    }
+
+    pub async fn list(&self) -> Result<()> {
+        let entries = self.entries().await?;
+        log_info!("Cache", "`{}`", self.dir.display());
+        if entries.is_empty() {
+            log_info!("Cache", "no cached NW distributions or headers");
+            return Ok(());
+        }
+
+        for entry in entries.iter() {
+            let last_used: DateTime<Local> = entry.last_used.into();
+            log_info!(
+                &entry.kind.to_string(),
+                "{:<12} {:<6} {:<6} {:>12}   last used {}",
+                entry.version,
+                entry.platform,
+                entry.arch,
+                style(format_size(entry.size)).cyan(),
+                last_used.format("%Y-%m-%d %H:%M")
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn size(&self) -> Result<()> {
+        let entries = self.entries().await?;
+        let distributions = entries.iter().map(|entry| entry.size).sum::<u64>();
+        let temp = folder_size(&self.temp_folder());
+        let total = folder_size(&self.dir);
+
+        log_info!("Cache", "`{}`", self.dir.display());
+        log_info!(
+            "Cache",
+            "distributions: {} ({} entries)",
+            style(format_size(distributions)).cyan(),
+            entries.len()
+        );
+        log_info!("Cache", "temp: {}", style(format_size(temp)).cyan());
+        log_info!("Cache", "total: {}", style(format_size(total)).cyan());
+
+        Ok(())
+    }
+
+    pub async fn prune(&self, options: &PruneOptions) -> Result<()> {
+        let entries = self.entries().await?;
+
+        let keep_versions = options.keep.map(|keep| {
+            let mut versions = entries
+                .iter()
+                .map(|entry| entry.version.clone())
+                .collect::<Vec<_>>();
+            versions.dedup();
+            versions.truncate(keep);
+            versions
+        });
+
+        let now = Utc::now();
+        let mut freed = 0;
+        for entry in entries.iter() {
+            let expired = options
+                .older_than
+                .map(|age| now - entry.last_used > age)
+                .unwrap_or(false);
+            let superseded = keep_versions
+                .as_ref()
+                .map(|versions| !versions.contains(&entry.version))
+                .unwrap_or(false);
+
+            if expired || superseded {
+                freed += entry.size;
+                if options.dry_run {
+                    log_info!("Prune", "would remove {entry}");
+                } else {
+                    entry.remove().await?;
+                }
+            }
+        }
+
+        freed += self
+            .prune_temp(
+                options.older_than.unwrap_or_else(|| Duration::days(7)),
+                options.dry_run,
+            )
+            .await?;
+
+        log_info!(
+            "Prune",
+            "{} {}",
+            if options.dry_run {
+                "would free"
+            } else {
+                "freed"
+            },
+            style(format_size(freed)).cyan()
+        );
+
+        Ok(())
+    }
+
+    /// Remove temp folders that have not been modified for the given duration
+    async fn prune_temp(&self, age: Duration, dry_run: bool) -> Result<u64> {
+        let temp_folder = self.temp_folder();
+        if !temp_folder.is_dir().await {
+            return Ok(0);
+        }
+
+        let now = Utc::now();
+        let mut freed = 0;
+        for entry in std::fs::read_dir(&temp_folder)?.flatten() {
+            let folder: PathBuf = entry.path().into();
+            if !folder.is_dir().await {
+                continue;
+            }
+
+            if now - last_modified(&folder) > age {
+                freed += folder_size(&folder);
+                if dry_run {
+                    log_info!("Prune", "would remove `{}`", folder.display());
+                } else {
+                    log_info!("Removing", "`{}`", folder.display());
+                    fs::remove_dir_all(&folder).await?;
+                }
+            }
+        }
+
+        Ok(freed)
+    }
+
+    /// Remove distributions matching the version (all versions if `None`),
+    /// optionally filtered by platform and architecture.
+    pub async fn remove(
+        &self,
+        version: Option<&str>,
+        platform: Option<&Platform>,
+        arch: Option<&Architecture>,
+    ) -> Result<()> {
+        let platform = platform.map(get_nwjs_suffix);
+        let arch = arch.map(|arch| arch.to_nwjs_arch());
+
+        let entries = self.entries().await?;
+        let entries = entries
+            .iter()
+            .filter(|entry| {
+                version
+                    .map(|version| entry.version == version.trim_start_matches('v'))
+                    .unwrap_or(true)
+                    && platform
+                        .as_ref()
+                        .map(|platform| &entry.platform == platform)
+                        .unwrap_or(true)
+                    && arch
+                        .as_ref()
+                        .map(|arch| &entry.arch == arch)
+                        .unwrap_or(true)
+            })
+            .collect::<Vec<_>>();
+
+        if entries.is_empty() {
+            return Err(Error::Warning("no matching cached distributions".into()));
+        }
+
+        for entry in entries {
+            entry.remove().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove all temp folders
+    pub async fn remove_temp(&self) -> Result<()> {
+        let temp_folder = self.temp_folder();
+        if temp_folder.is_dir().await {
+            log_info!("Removing", "`{}`", temp_folder.display());
+            fs::remove_dir_all(&temp_folder).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse duration in the `30d`, `12h`, `2w` format (days if no suffix is given)
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let (value, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => text.split_at(pos),
+        None => (text, "d"),
+    };
+    let value = value
+        .parse::<i64>()
+        .map_err(|_| format!("invalid duration `{text}`"))?;
+    match unit {
+        "h" => Ok(Duration::hours(value)),
+        "d" => Ok(Duration::days(value)),
+        "w" => Ok(Duration::weeks(value)),
+        _ => Err(format!("invalid duration `{text}` (use `h`, `d` or `w` suffix)").into()),
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    format!("{:.2}Mb", bytes as f64 / 1024.0 / 1024.0)
+}
+
+fn folder_size(folder: &Path) -> u64 {
+    WalkDir::new(folder)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Marker file recording when the distribution `folder` was last used
+fn last_used_file(folder: &Path) -> PathBuf {
+    PathBuf::from(format!("{}{LAST_USED_SUFFIX}", folder.display()))
+}
+
+async fn last_used(folder: &Path) -> DateTime<Utc> {
+    if let Ok(text) = fs::read_to_string(last_used_file(folder)).await {
+        if let Ok(time) = DateTime::parse_from_rfc3339(text.trim()) {
+            return time.into();
+        }
+    }
+    last_modified(folder)
+}
+
+fn last_modified(folder: &Path) -> DateTime<Utc> {
+    std::fs::metadata(folder)
+        .and_then(|metadata| metadata.modified())
+        .map(DateTime::<Utc>::from)
+        .unwrap_or_else(|_| Utc::now())
+}
@@ -0,0 +1,235 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use std::path::PathBuf as StdPathBuf;
+
+/// Subset of the `cargo metadata` output
+#[derive(Debug, Clone, Deserialize)]
+pub struct CargoMetadata {
+    pub target_directory: StdPathBuf,
+    pub packages: Vec<CargoPackage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CargoPackage {
+    pub name: String,
+    pub manifest_path: StdPathBuf,
+    pub targets: Vec<CargoTarget>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CargoTarget {
+    pub name: String,
+    pub kind: Vec<String>,
+}
+
+/// Subset of `Cargo.toml` read by [`CargoMetadata::load_offline`]
+#[derive(Debug, Deserialize)]
+struct CargoToml {
+    package: Option<CargoTomlPackage>,
+    workspace: Option<CargoTomlWorkspace>,
+    bin: Option<Vec<CargoTomlBin>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoTomlPackage {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoTomlWorkspace {
+    members: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoTomlBin {
+    name: String,
+}
+
+impl CargoToml {
+    /// Load `Cargo.toml` located in `folder` (`None` if there is none)
+    async fn load(folder: &Path) -> Result<Option<CargoToml>> {
+        let file = folder.join("Cargo.toml");
+        if !file.is_file().await {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&file).await?;
+        let cargo_toml = toml::from_str(&text)
+            .map_err(|err| format!("unable to parse `{}`: {err}", file.display()))?;
+        Ok(Some(cargo_toml))
+    }
+}
+
+impl CargoMetadata {
+    pub fn target_directory(&self) -> PathBuf {
+        self.target_directory.clone().into()
+    }
+
+    /// Run `cargo metadata` for the workspace containing `folder`
+    pub fn load(folder: &Path) -> Result<CargoMetadata> {
+        let text = cmd!("cargo", "metadata", "--format-version", "1", "--no-deps")
+            .dir(folder)
+            .stderr_null()
+            .read()
+            .map_err(|err| {
+                format!(
+                    "unable to run `cargo metadata` in `{}`: {err}",
+                    folder.display()
+                )
+            })?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Resolve the metadata of the workspace containing `folder` from its
+    /// `Cargo.toml` files without running cargo (`--plan`). Binary targets
+    /// are the `[[bin]]` entries, `src/main.rs` and `src/bin/*.rs`; the
+    /// target directory is `CARGO_TARGET_DIR` or the workspace `target`.
+    pub async fn load_offline(folder: &Path) -> Result<CargoMetadata> {
+        let mut root = folder.to_path_buf();
+        let mut current = Some(folder.to_path_buf());
+        let mut members = Vec::new();
+        while let Some(dir) = current {
+            if let Some(CargoToml {
+                workspace: Some(workspace),
+                ..
+            }) = CargoToml::load(&dir).await?
+            {
+                members = workspace.members.unwrap_or_default();
+                root = dir;
+                break;
+            }
+            current = dir.parent().map(Path::to_path_buf);
+        }
+
+        let mut folders = vec![folder.to_path_buf(), root.clone()];
+        for member in members.iter() {
+            match member.strip_suffix("/*") {
+                Some(parent) => {
+                    for entry in std::fs::read_dir(root.join(parent))?.flatten() {
+                        folders.push(entry.path().into());
+                    }
+                }
+                None => folders.push(root.join(member)),
+            }
+        }
+
+        let mut packages = Vec::new();
+        for folder in folders.iter() {
+            let Some(CargoToml {
+                package: Some(package),
+                bin,
+                ..
+            }) = CargoToml::load(folder).await?
+            else {
+                continue;
+            };
+            if packages
+                .iter()
+                .any(|known: &CargoPackage| known.name == package.name)
+            {
+                continue;
+            }
+
+            let mut binaries = bin
+                .unwrap_or_default()
+                .into_iter()
+                .map(|bin| bin.name)
+                .collect::<Vec<_>>();
+            if folder.join("src/main.rs").is_file().await {
+                binaries.push(package.name.clone());
+            }
+            if let Ok(entries) = std::fs::read_dir(folder.join("src/bin")) {
+                for path in entries.flatten().map(|entry| entry.path()) {
+                    if path.extension().is_some_and(|ext| ext == "rs") {
+                        binaries.extend(path.file_stem().map(|s| s.to_string_lossy().to_string()));
+                    }
+                }
+            }
+            binaries.sort();
+            binaries.dedup();
+
+            packages.push(CargoPackage {
+                name: package.name,
+                manifest_path: folder.join("Cargo.toml").into(),
+                targets: binaries
+                    .into_iter()
+                    .map(|name| CargoTarget {
+                        name,
+                        kind: vec!["bin".to_string()],
+                    })
+                    .collect(),
+            });
+        }
+
+        let target_directory = match std::env::var("CARGO_TARGET_DIR") {
+            Ok(folder) => root.join(folder),
+            Err(_) => root.join("target"),
+        };
+
+        Ok(CargoMetadata {
+            target_directory: target_directory.into(),
+            packages,
+        })
+    }
+
+    /// Package with the given name or, if `None`, the
+    /// package whose `Cargo.toml` is located in `folder`
+    pub fn package(&self, name: Option<&str>, folder: &Path) -> Result<&CargoPackage> {
+        match name {
+            Some(name) => self
+                .packages
+                .iter()
+                .find(|package| package.name == name)
+                .ok_or_else(|| format!("cargo package `{name}` not found").into()),
+            None => {
+                let manifest_path: StdPathBuf = folder.join("Cargo.toml").into();
+                self.packages
+                    .iter()
+                    .find(|package| package.manifest_path == manifest_path)
+                    .ok_or_else(|| {
+                        format!(
+                            "no cargo package found in `{}`, please specify the package name",
+                            folder.display()
+                        )
+                        .into()
+                    })
+            }
+        }
+    }
+
+    /// Names of binary targets of the package
+    pub fn binaries(&self, package: Option<&str>, folder: &Path) -> Result<Vec<String>> {
+        let package = self.package(package, folder)?;
+        let binaries = package
+            .targets
+            .iter()
+            .filter(|target| target.kind.iter().any(|kind| kind == "bin"))
+            .map(|target| target.name.clone())
+            .collect::<Vec<_>>();
+
+        if binaries.is_empty() {
+            return Err(format!("cargo package `{}` has no binary targets", package.name).into());
+        }
+
+        Ok(binaries)
+    }
+}
+
+/// File name of the cargo binary `bin` built for the target `platform`
+pub fn executable_file_name(bin: &str, platform: &Platform) -> String {
+    match platform {
+        Platform::Windows => format!("{bin}.exe"),
+        _ => bin.to_string(),
+    }
+}
+
+/// Copy an executable file (the source permissions are retained)
+pub async fn copy_executable(src: &Path, dest: &Path) -> Result<()> {
+    if !src.is_file().await {
+        return Err(format!("executable `{}` not found", src.display()).into());
+    }
+
+    fs::copy(src, dest).await?;
+
+    Ok(())
+}
@@ -2,13 +2,26 @@ use crate::prelude::*;
 use async_std::path::Path;
 use async_std::path::PathBuf;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Options {
     pub sdk: bool,
     pub nwjs_version_override: Option<String>,
     pub dry_run: bool,
     pub channel: Option<Channel>,
     pub confinement: Option<Confinement>,
+    /// Require `nw.lock` to be up to date (`--locked`)
+    pub locked: bool,
+    /// Ignore locked versions and refresh `nw.lock` (`cargo nw update`)
+    pub update: bool,
+    /// Execute build steps even if they are up to date (`--force`)
+    pub force: bool,
+    /// Resolve without network access or package manager and
+    /// `cargo metadata` invocations (`--plan`)
+    pub offline: bool,
+    /// Build variant (`[[variant]]` manifest section)
+    pub variant: Option<String>,
+    /// White-label flavor (`[[flavor]]` manifest section)
+    pub flavor: Option<String>,
 }
 
 // impl Default for Options {
@@ -45,8 +58,16 @@ pub struct Context {
     pub output_folder: PathBuf,
     pub temp_folder: PathBuf,
     pub dependencies_folder: PathBuf,
+    /// Fingerprints of executed build steps
+    pub fingerprint_folder: PathBuf,
+    /// Intermediate build step data retained between builds
+    pub staging_folder: PathBuf,
 
     pub app_snake_name: String,
+    /// Build variant applied to the manifest
+    pub variant: Option<Variant>,
+    /// White-label flavor applied to the manifest
+    pub flavor: Option<Flavor>,
 
     pub include: Option<Vec<CopyFilter>>,
     pub exclude: Option<Vec<CopyFilter>>,
@@ -55,9 +76,13 @@ pub struct Context {
 
     pub sdk: bool,
     pub dry_run: bool,
+    pub update: bool,
+    pub force: bool,
+    pub offline: bool,
     pub channel: Channel,
     pub confinement: Confinement,
     pub deps: Deps,
+    pub lock: Lockfile,
     pub tpl: Tpl,
 }
 
@@ -101,8 +126,27 @@ impl Context {
         let manifest_toml = Manifest::locate(location).await?;
         log_info!("Manifest", "`{}`", manifest_toml.to_str().unwrap());
         let manifest_folder = manifest_toml.parent().unwrap().to_path_buf();
-        let manifest = Manifest::load(&manifest_toml).await?;
+        let mut manifest = Manifest::load(&manifest_toml).await?;
         let project_root = manifest_toml.parent().unwrap();
+        let base_name = manifest.application.name.clone();
+        let flavor = options
+            .flavor
+            .as_deref()
+            .map(|name| manifest.apply_flavor(name))
+            .transpose()?;
+        if let Some(flavor) = &flavor {
+            log_info!("Flavor", "`{}`", flavor.name);
+            tpl.set(&[("FLAVOR", flavor.name.as_str())]);
+        }
+        let variant = options
+            .variant
+            .as_deref()
+            .map(|name| manifest.apply_variant(name))
+            .transpose()?;
+        if let Some(variant) = &variant {
+            log_info!("Variant", "`{}`", variant.name);
+            tpl.set(&[("VARIANT", variant.name.as_str())]);
+        }
 
         tpl.set(&[
             ("NAME", manifest.application.name.as_str()),
@@ -136,9 +180,22 @@ impl Context {
                 .unwrap_or_else(|| manifest_folder.clone())
         };
 
+        // flavors retaining the application name are distinguished by the flavor name
+        let flavor_suffix = match &flavor {
+            Some(flavor) if manifest.application.name == base_name => format!("-{}", flavor.name),
+            _ => String::new(),
+        };
         let app_snake_name = format!(
-            "{}-{}-{}-{}",
-            manifest.application.name, manifest.application.version, platform, arch
+            "{}{}{}-{}-{}-{}",
+            manifest.application.name,
+            flavor_suffix,
+            variant
+                .as_ref()
+                .map(|variant| variant.suffix())
+                .unwrap_or_default(),
+            manifest.application.version,
+            platform,
+            arch
         );
 
         let cargo_target_folder = root_folder.join("target");
@@ -152,6 +209,12 @@ impl Context {
         let dependencies_folder = Path::new(&cargo_nw_target_folder)
             .join("deps")
             .join(&app_snake_name);
+        let fingerprint_folder = Path::new(&cargo_nw_target_folder)
+            .join("fingerprint")
+            .join(&app_snake_name);
+        let staging_folder = Path::new(&cargo_nw_target_folder)
+            .join("staging")
+            .join(&app_snake_name);
 
         let output_folder = if let Some(output) = output.or_else(|| manifest.package.output.clone())
         {
@@ -164,6 +227,10 @@ impl Context {
         } else {
             Path::new(&cargo_nw_target_folder).join("setup")
         };
+        let output_folder = match &flavor {
+            Some(flavor) => output_folder.join(&flavor.name),
+            None => output_folder,
+        };
         let output_folder = normalize(tpl.transform(output_folder.to_str().unwrap()))?;
         tpl.set(&[
             ("OUTPUT", output_folder.to_str().unwrap()),
@@ -210,19 +277,64 @@ impl Context {
         );
         let sdk = manifest.nwjs.sdk.unwrap_or(options.sdk);
         let dry_run = options.dry_run;
+        let update = options.update;
         let snap = manifest.snap.clone().unwrap_or_default();
         let channel = options.channel.or(snap.channel).unwrap_or_default();
         let confinement = options.confinement.or(snap.confinement).unwrap_or_default();
-        let deps = Deps::new(
-            &platform,
-            &arch,
-            &manifest,
-            sdk,
-            options.nwjs_version_override,
-        );
+        let nwjs_version = options
+            .nwjs_version_override
+            .unwrap_or_else(|| manifest.nwjs.version());
+        let lock = Lockfile::load(&manifest_folder, options.locked).await?;
+        let (release, resolved) = match lock.release(&nwjs_version) {
+            Some(release) if !options.update => (release, false),
+            _ if options.offline => {
+                let release = NwRelease::resolve_offline(
+                    &nwjs_version,
+                    &manifest.nwjs,
+                    &manifest_folder,
+                    &platform,
+                    &arch,
+                )
+                .await?;
+                (release, false)
+            }
+            _ => {
+                let release = NwRelease::resolve(
+                    &nwjs_version,
+                    &manifest.nwjs,
+                    &manifest_folder,
+                    &platform,
+                    &arch,
+                )
+                .await?;
+                let resolved = release.is_resolved();
+                (release, resolved)
+            }
+        };
+        if !release.is_unresolved() {
+            lock.set_release(&release)?;
+        }
+        tpl.set(&[("NW_VERSION", release.version.as_str())]);
+        if let Some(chromium) = &release.chromium {
+            tpl.set(&[("NW_CHROMIUM", chromium.as_str())]);
+        }
+        if let Some(node) = &release.node {
+            tpl.set(&[("NW_NODE", node.as_str())]);
+        }
+
+        let deps = Deps::new(&platform, &arch, &manifest, sdk, release);
+        if resolved && !options.offline {
+            deps.check_ffmpeg().await?;
+        }
 
         let include = manifest.package.include.clone(); //.unwrap_or(vec![]);
-        let exclude = manifest.package.exclude.clone(); //.unwrap_or(vec![]);
+        let mut exclude = manifest.package.exclude.clone(); //.unwrap_or(vec![]);
+        if manifest.npm_installs_into_package() {
+            // production packages are installed into the package by the `NPM` build
+            exclude
+                .get_or_insert_with(Vec::new)
+                .push(CopyFilter::Glob(vec!["node_modules/**".to_string()]));
+        }
 
         let images = manifest.images.clone().unwrap_or_default();
 
@@ -235,6 +347,8 @@ impl Context {
             arch,
             home_folder,
             app_snake_name,
+            variant,
+            flavor,
             app_root_folder,
             project_root_folder,
             setup_resources_folder,
@@ -245,6 +359,8 @@ impl Context {
             cache_folder,
             temp_folder,
             dependencies_folder,
+            fingerprint_folder,
+            staging_folder,
             output_folder,
 
             include,
@@ -254,9 +370,13 @@ impl Context {
             // app_root_folder,
             sdk,
             dry_run,
+            update,
+            force: options.force,
+            offline: options.offline,
             channel,
             confinement,
             deps,
+            lock,
             tpl, // : Arc::new(Mutex::new(tpl)),
         };
 
@@ -286,6 +406,17 @@ impl Context {
         }
         Ok(())
     }
+    /// Remove build step fingerprints and staging data
+    pub async fn clean_build_steps(&self) -> Result<()> {
+        for folder in [&self.fingerprint_folder, &self.staging_folder] {
+            if folder.exists().await {
+                log_info!("Cleaning", "`{}`", folder.display());
+                async_std::fs::remove_dir_all(folder).await?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn clean(&self) -> Result<()> {
         if self.build_folder.exists().await {
             log_info!("Cleaning", "`{}`", self.build_folder.display());
@@ -302,6 +433,30 @@ impl Context {
         self.tpl.clone()
     }
 
+    /// Copy the flavor overlay folder into the application package
+    pub async fn copy_flavor_overlay(&self, target_folder: &Path) -> Result<()> {
+        let Some(overlay) = self
+            .flavor
+            .as_ref()
+            .and_then(|flavor| flavor.overlay.as_ref())
+        else {
+            return Ok(());
+        };
+
+        let folder = self.project_root_folder.join(overlay);
+        if !folder.is_dir().await {
+            return Err(format!("flavor overlay folder `{}` not found", folder.display()).into());
+        }
+
+        log_info!("Flavor", "applying overlay `{overlay}`");
+        let mut options = fs_extra::dir::CopyOptions::new();
+        options.content_only = true;
+        options.overwrite = true;
+        fs_extra::dir::copy(&folder, target_folder, &options)?;
+
+        Ok(())
+    }
+
     pub async fn update_package_json(&self, target_folder: &Path) -> Result<()> {
         if self.manifest.package.update_package_json.unwrap_or(false) {
             log_info!("Manifest", "Updating package.json manifest");
@@ -233,15 +233,13 @@ impl Rename {
     }
 }
 
-pub async fn copy_folder_with_filters(
+/// Files of `src_folder` (relative to `src_folder`) matching `filters`
+pub fn list_files_with_filters(
     src_folder: &Path,
-    dest_folder: &Path,
-    // to : Option<String>,
-    filters: Filters,
-    options: CopyOptions,
-) -> Result<()> {
-    // let list = WalkDir::new(src_folder)
-    let list = WalkDir::new(src_folder)
+    filters: &Filters,
+    options: &CopyOptions,
+) -> Vec<PathBuf> {
+    WalkDir::new(src_folder)
         .into_iter()
         .flatten()
         .filter_map(|entry| {
@@ -257,17 +255,27 @@ pub async fn copy_folder_with_filters(
             } else {
                 None
             }
-        });
+        })
+        .collect()
+}
+
+pub async fn copy_folder_with_filters(
+    src_folder: &Path,
+    dest_folder: &Path,
+    // to : Option<String>,
+    filters: Filters,
+    options: CopyOptions,
+) -> Result<()> {
+    let list = list_files_with_filters(src_folder, &filters, &options);
 
     let rename = Rename::try_from(dest_folder);
 
     if options.flatten {
-        let files: Vec<_> = list.collect();
-        if !files.is_empty() {
+        if !list.is_empty() {
             std::fs::create_dir_all(dest_folder)?;
         }
 
-        for file in files {
+        for file in list {
             let mut to_file = dest_folder.join(file.file_name().unwrap());
             if let Some(rename) = &rename {
                 rename.transform(&mut to_file);
@@ -282,7 +290,6 @@ pub async fn copy_folder_with_filters(
         }
     } else {
         let mut folders = HashSet::new();
-        let list: Vec<_> = list.collect();
         for path in list.iter() {
             let folder = path.parent().unwrap();
             folders.insert(folder.to_path_buf());
@@ -1,12 +1,6 @@
 use crate::prelude::*;
 use async_std::path::Path;
 use async_std::path::PathBuf;
-use trauma::{
-    download::Download,
-    download::Status,
-    // Error,
-    downloader::DownloaderBuilder,
-};
 
 // fn to_target(dir: &PathBuf, folder: &str) -> PathBuf {
 //     Path::new(dir).join(folder)//.into_os_string().into_string().unwrap()
@@ -56,14 +50,18 @@ pub fn get_nwjs_archive_extension(platform: &Platform) -> String {
     .into()
 }
 
+pub fn get_deps_folder() -> PathBuf {
+    let home_dir: PathBuf = home::home_dir().unwrap().into();
+    Path::new(&home_dir).join(".cargo-nw")
+}
+
 pub fn get_nwjs_ffmpeg_meta(
     platform: &Platform,
     arch: &Architecture,
-    manifest: &Manifest,
+    version: &str,
     target: &PathBuf,
 ) -> Meta {
     let arch = arch.to_nwjs_arch();
-    let version = &manifest.nwjs.version();
     let suffix = get_nwjs_suffix(platform);
     let folder = format!("ffmpeg-{version}-{suffix}-{arch}");
     let file = format!("{version}-{suffix}-{arch}.zip");
@@ -76,12 +74,11 @@ pub fn get_nwjs_ffmpeg_meta(
 pub fn get_nwjs_sdk_meta(
     platform: &Platform,
     arch: &Architecture,
-    manifest: &Manifest,
+    version: &str,
     target: &PathBuf,
-    version_override: Option<String>,
 ) -> Meta {
     let arch = arch.to_nwjs_arch();
-    let version = format!("v{}", version_override.unwrap_or(manifest.nwjs.version()));
+    let version = format!("v{version}");
     let suffix = get_nwjs_suffix(platform);
     let folder = format!("nwjs-sdk-{version}-{suffix}-{arch}");
     let archive_extension = get_nwjs_archive_extension(platform);
@@ -93,12 +90,11 @@ pub fn get_nwjs_sdk_meta(
 pub fn get_nwjs_meta(
     platform: &Platform,
     arch: &Architecture,
-    manifest: &Manifest,
+    version: &str,
     target: &PathBuf,
-    version_override: Option<String>,
 ) -> Meta {
     let arch = arch.to_nwjs_arch();
-    let version = format!("v{}", version_override.unwrap_or(manifest.nwjs.version()));
+    let version = format!("v{version}");
     let suffix = get_nwjs_suffix(platform);
     let folder = format!("nwjs-{version}-{suffix}-{arch}");
     let archive_extension = get_nwjs_archive_extension(platform);
@@ -112,6 +108,8 @@ pub struct Deps {
     pub ffmpeg: Option<Meta>,
     pub nwjs: Meta,
     pub dir: PathBuf,
+    pub release: NwRelease,
+    pub download: Option<Download>,
 }
 
 impl Deps {
@@ -120,27 +118,59 @@ impl Deps {
         arch: &Architecture,
         manifest: &Manifest,
         sdk: bool,
-        nwjs_version_override: Option<String>,
+        release: NwRelease,
     ) -> Deps {
-        let home_dir: PathBuf = home::home_dir().unwrap().into();
-        let dir: PathBuf = Path::new(&home_dir).join(".cargo-nw");
+        let dir = get_deps_folder();
+        let version = release.version.as_str();
 
         let nwjs = if sdk {
-            get_nwjs_sdk_meta(platform, arch, manifest, &dir, nwjs_version_override)
+            get_nwjs_sdk_meta(platform, arch, version, &dir)
         } else {
-            get_nwjs_meta(platform, arch, manifest, &dir, nwjs_version_override)
+            get_nwjs_meta(platform, arch, version, &dir)
         };
 
         let ffmpeg = if manifest.nwjs.ffmpeg.unwrap_or(false) {
-            Some(get_nwjs_ffmpeg_meta(platform, arch, manifest, &dir))
+            Some(get_nwjs_ffmpeg_meta(platform, arch, version, &dir))
         } else {
             None
         };
 
-        Deps { dir, nwjs, ffmpeg }
+        Deps {
+            dir,
+            nwjs,
+            ffmpeg,
+            release,
+            download: manifest.download.clone(),
+        }
+    }
+
+    /// Warn if the FFMPEG prebuilt release matching the NW
+    /// version is not available for download.
+    pub async fn check_ffmpeg(&self) -> Result<()> {
+        if let Some(ffmpeg) = &self.ffmpeg {
+            if self.dir.join(&ffmpeg.folder).exists().await {
+                return Ok(());
+            }
+
+            let available = match reqwest::Client::new().head(&ffmpeg.url).send().await {
+                Ok(response) => response.status().is_success(),
+                Err(_) => false,
+            };
+
+            if !available {
+                log_warn!(
+                    "FFMPEG",
+                    "prebuilt release for NW version `{}` is not available",
+                    self.release.version
+                );
+                log_warn!("FFMPEG", "`{}`", ffmpeg.url);
+            }
+        }
+
+        Ok(())
     }
 
-    fn get_targets(&self) -> Vec<Meta> {
+    pub fn get_targets(&self) -> Vec<Meta> {
         let mut list = Vec::new();
         list.push(self.nwjs.clone());
         if let Some(ffmpeg) = &self.ffmpeg {
@@ -149,72 +179,77 @@ impl Deps {
         list
     }
 
+    /// Remove NW distributions used by this project
+    /// (see `cargo nw cache` for management of all cached versions)
     pub async fn clean(&self) -> Result<()> {
-        if self.dir.exists().await {
-            log_info!("Cleaning", "`{}`", self.dir.display());
-            async_std::fs::remove_dir_all(&self.dir).await?;
+        for meta in self.get_targets() {
+            let _lock = self.lock(&meta).await?;
+            let folder = self.dir.join(&meta.folder);
+            if folder.exists().await {
+                log_info!("Cleaning", "`{}`", folder.display());
+                async_std::fs::remove_dir_all(&folder).await?;
+            }
+            let file = self.dir.join(&meta.file);
+            if file.exists().await {
+                log_info!("Cleaning", "`{}`", file.display());
+                async_std::fs::remove_file(&file).await?;
+            }
         }
 
         Ok(())
     }
 
-    pub async fn ensure(&self) -> Result<()> {
+    /// Lock the distribution against concurrent download,
+    /// extraction or removal by other `cargo-nw` processes
+    async fn lock(&self, meta: &Meta) -> Result<FileLock> {
+        FileLock::acquire(
+            &self.dir.join(format!("{}.lock", meta.folder)),
+            &format!("`{}`", meta.folder),
+        )
+        .await
+    }
+
+    /// Download and extract the NW distributions. Archives are verified
+    /// against `nw.lock` before extraction (see [`Lockfile::verify_archive`]).
+    pub async fn ensure(&self, lock: &Lockfile, update: bool) -> Result<()> {
         // log!("Dependencies","checking");
         let targets = self.get_targets();
         // println!("targets: {:?}", targets);
 
-        let downloads = targets
-            .iter()
-            .filter(|meta| !std::path::Path::new(&self.dir).join(&meta.folder).exists())
-            .collect::<Vec<&Meta>>();
-
-        if !downloads.is_empty() {
-            log_info!("Dependencies", "... downloading NW dependencies ...");
-            // println!("");
+        let mut downloader = None;
+        for meta in targets.iter() {
+            let _lock = self.lock(meta).await?;
 
-            self.download(&downloads).await?;
-            println!();
+            let file = Path::new(&self.dir).join(&meta.file);
+            // the distribution may have been installed while waiting for the lock
+            if !self.dir.join(&meta.folder).exists().await {
+                if downloader.is_none() {
+                    log_info!("Dependencies", "... downloading NW dependencies ...");
+                    downloader = Some(Downloader::new(self.download.as_ref())?);
+                }
+                self.download(downloader.as_ref().unwrap(), meta).await?;
+                lock.verify_archive(meta, &file, update)?;
 
-            for meta in downloads {
                 log_info!("Dependencies", "extracting {}", &meta.file);
-                let file = Path::new(&self.dir).join(&meta.file);
                 // let target_dir = meta.get_extract_path(&self.dir);
                 extract(&file, &meta.target.clone()).await?;
+            } else if file.exists().await {
+                lock.verify_archive(meta, &file, update)?;
             }
-        } else {
-            // log!("Dependencies","ok");
+
+            Cache::touch(&self.dir.join(&meta.folder)).await?;
         }
 
         Ok(())
     }
 
-    async fn download(&self, list: &[&Meta]) -> Result<()> {
-        let downloads: Vec<Download> = list
-            .iter()
-            .map(|meta| Download::try_from(meta.url.as_str()).unwrap())
-            .collect();
-        // let reqwest_rs = "https://github.com/seanmonstar/reqwest/archive/refs/tags/v0.11.9.zip";
-        // let downloads = vec![Download::try_from(reqwest_rs).unwrap()];
-        let downloader = DownloaderBuilder::new()
-            .directory(self.dir.clone().into())
-            // .directory(PathBuf::from("output").into())
-            .build();
-        let slist = downloader.download(&downloads).await;
-
-        for summary in slist.iter() {
-            match summary.status() {
-                Status::Fail(e) => return Err(Error::String(e.into())),
-                Status::NotStarted => {
-                    return Err(
-                        format!("Unable to start download for: {}", summary.download().url).into(),
-                    )
-                }
-                Status::Skipped(msg) => {
-                    log_info!("Dependencies", "{}", msg);
-                    // return Err(Error::String(e.into()))
-                }
-                Status::Success => {}
-            }
+    async fn download(&self, downloader: &Downloader, meta: &Meta) -> Result<()> {
+        async_std::fs::create_dir_all(&self.dir).await?;
+        let file = self.dir.join(&meta.file);
+        if file.exists().await {
+            log_info!("Dependencies", "using downloaded `{}`", meta.file);
+        } else {
+            downloader.download(&meta.url, &file).await?;
         }
 
         Ok(())
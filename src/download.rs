@@ -0,0 +1,224 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::future::timeout;
+use async_std::io::WriteExt;
+use async_std::path::{Path, PathBuf};
+use async_std::task::sleep;
+use reqwest::{header, NoProxy, Proxy, StatusCode};
+use std::time::{Duration, Instant};
+
+const DEFAULT_RETRIES: u32 = 5;
+const DEFAULT_BACKOFF: u64 = 2;
+const DEFAULT_CONNECT_TIMEOUT: u64 = 30;
+const DEFAULT_READ_TIMEOUT: u64 = 60;
+
+/// Download failure; `Retry` failures are transient and
+/// the download is re-attempted after a backoff delay.
+enum Failure {
+    Retry(String),
+    Fatal(String),
+}
+
+/// HTTP downloader with retries, resume of partial
+/// downloads, timeouts and proxy configuration.
+pub struct Downloader {
+    client: reqwest::Client,
+    retries: u32,
+    backoff: Duration,
+    read_timeout: Duration,
+}
+
+impl Downloader {
+    pub fn new(settings: Option<&Download>) -> Result<Downloader> {
+        let settings = settings.cloned().unwrap_or_default();
+
+        let mut builder = reqwest::Client::builder().connect_timeout(Duration::from_secs(
+            settings.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+        ));
+
+        if let Some(proxy) = &settings.proxy {
+            let no_proxy = settings
+                .no_proxy
+                .as_deref()
+                .and_then(NoProxy::from_string)
+                .or_else(NoProxy::from_env);
+            let proxy = Proxy::all(proxy)
+                .map_err(|err| format!("invalid download proxy `{proxy}`: {err}"))?
+                .no_proxy(no_proxy);
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(Downloader {
+            client: builder.build()?,
+            retries: settings.retries.unwrap_or(DEFAULT_RETRIES),
+            backoff: Duration::from_secs(settings.backoff.unwrap_or(DEFAULT_BACKOFF)),
+            read_timeout: Duration::from_secs(
+                settings.read_timeout.unwrap_or(DEFAULT_READ_TIMEOUT),
+            ),
+        })
+    }
+
+    /// Download `url` into `file`. Data is received into a `.part` file
+    /// which is resumed (using an HTTP range request) if a previous
+    /// attempt was interrupted.
+    pub async fn download(&self, url: &str, file: &Path) -> Result<()> {
+        let name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| url.to_string());
+
+        let mut attempt = 0;
+        loop {
+            match self.try_download(url, file, &name).await {
+                Ok(()) => return Ok(()),
+                Err(Failure::Fatal(err)) => {
+                    log_state_clear();
+                    return Err(format!("unable to download `{url}`: {err}").into());
+                }
+                Err(Failure::Retry(err)) if attempt < self.retries => {
+                    log_state_clear();
+                    let delay = self.backoff * 2u32.pow(attempt);
+                    attempt += 1;
+                    log_warn!("Download", "`{name}`: {err}");
+                    log_warn!(
+                        "Download",
+                        "retrying in {}s ({attempt}/{})",
+                        delay.as_secs(),
+                        self.retries
+                    );
+                    sleep(delay).await;
+                }
+                Err(Failure::Retry(err)) => {
+                    log_state_clear();
+                    return Err(format!(
+                        "unable to download `{url}` after {} attempts: {err}",
+                        attempt + 1
+                    )
+                    .into());
+                }
+            }
+        }
+    }
+
+    async fn try_download(
+        &self,
+        url: &str,
+        file: &Path,
+        name: &str,
+    ) -> std::result::Result<(), Failure> {
+        let part = partial_file(file);
+        let offset = fs::metadata(&part)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            request = request.header(header::RANGE, format!("bytes={offset}-"));
+        }
+
+        let mut response = request
+            .send()
+            .await
+            .map_err(|err| Failure::Retry(err.to_string()))?;
+
+        let status = response.status();
+        let (mut out, mut received) = match status {
+            StatusCode::PARTIAL_CONTENT => {
+                log_info!("Download", "resuming `{name}` at {}", format_size(offset));
+                let out = fs::OpenOptions::new()
+                    .append(true)
+                    .open(&part)
+                    .await
+                    .map_err(|err| Failure::Fatal(err.to_string()))?;
+                (out, offset)
+            }
+            StatusCode::RANGE_NOT_SATISFIABLE => {
+                fs::remove_file(&part).await.ok();
+                return Err(Failure::Retry("unable to resume partial download".into()));
+            }
+            status if status.is_success() => {
+                let out = fs::File::create(&part)
+                    .await
+                    .map_err(|err| Failure::Fatal(err.to_string()))?;
+                (out, 0)
+            }
+            status
+                if status.is_server_error()
+                    || status == StatusCode::TOO_MANY_REQUESTS
+                    || status == StatusCode::REQUEST_TIMEOUT =>
+            {
+                return Err(Failure::Retry(format!("HTTP status {status}")));
+            }
+            status => {
+                return Err(Failure::Fatal(format!("HTTP status {status}")));
+            }
+        };
+
+        let total = response.content_length().map(|len| len + received);
+        let mut last_update = Instant::now();
+        loop {
+            let chunk = timeout(self.read_timeout, response.chunk())
+                .await
+                .map_err(|_| {
+                    Failure::Retry(format!(
+                        "no data received for {}s",
+                        self.read_timeout.as_secs()
+                    ))
+                })?
+                .map_err(|err| Failure::Retry(err.to_string()))?;
+
+            let Some(chunk) = chunk else {
+                break;
+            };
+
+            out.write_all(&chunk)
+                .await
+                .map_err(|err| Failure::Fatal(err.to_string()))?;
+            received += chunk.len() as u64;
+
+            if last_update.elapsed() > Duration::from_millis(100) {
+                last_update = Instant::now();
+                match total {
+                    Some(total) if total > 0 => log_state!(
+                        "Download",
+                        "`{name}` {}% ({} / {})",
+                        received * 100 / total,
+                        format_size(received),
+                        format_size(total)
+                    ),
+                    _ => log_state!("Download", "`{name}` {}", format_size(received)),
+                }
+            }
+        }
+
+        out.flush()
+            .await
+            .map_err(|err| Failure::Fatal(err.to_string()))?;
+        drop(out);
+        log_state_clear();
+
+        if let Some(total) = total {
+            if received < total {
+                return Err(Failure::Retry(format!(
+                    "incomplete download ({} of {})",
+                    format_size(received),
+                    format_size(total)
+                )));
+            }
+        }
+
+        fs::rename(&part, file)
+            .await
+            .map_err(|err| Failure::Fatal(err.to_string()))?;
+        log_info!("Download", "`{name}` {}", format_size(received));
+
+        Ok(())
+    }
+}
+
+fn partial_file(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_os_string();
+    name.push(".part");
+    name.into()
+}
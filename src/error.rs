@@ -35,9 +35,8 @@ pub enum Error {
     #[error("FileSystem error: {0}")]
     FsExtra(#[from] fs_extra::error::Error),
 
-    #[cfg(any(target_os = "windows", feature = "multiplatform"))]
+    #[cfg(target_os = "windows")]
     #[error("Windows resource error: {0}")]
-    #[cfg(any(target_os = "windows", feature = "multiplatform"))]
     WinRes(#[from] winres_edit::Error),
 
     #[error("Glob error: {0}")]
@@ -64,8 +63,14 @@ pub enum Error {
     #[error("'description.short' length must be less than 78 characters")]
     ShortDescriptionIsTooLong,
 
+    #[error("HTTP error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+
     #[error("Toml Deserialize: {0}")]
     TomlDeserialize(#[from] toml::de::Error),
+
+    #[error("Toml Serialize: {0}")]
+    TomlSerialize(#[from] toml::ser::Error),
     // #[error("Error: {0}")]
     // TryFromSliceError(#[from] TryFromSliceError),
 }
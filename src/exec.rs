@@ -1,5 +1,5 @@
 use crate::prelude::*;
-use async_std::path::Path;
+use async_std::path::{Path, PathBuf};
 
 pub enum ExecArgs {
     String(String),
@@ -48,14 +48,66 @@ impl From<Vec<&str>> for ExecArgs {
     }
 }
 
+/// Child process output handling
+pub trait StdoutToLog {
+    /// Route the child process stdout to the log output, i.e. to stderr
+    /// if JSON messages are enabled, keeping stdout reserved for them
+    fn stdout_to_log(self) -> Self;
+}
+
+impl StdoutToLog for duct::Expression {
+    fn stdout_to_log(self) -> Self {
+        if json_messages() {
+            self.stdout_to_stderr()
+        } else {
+            self
+        }
+    }
+}
+
 pub async fn execute_with_context(
     ctx: &Context,
     ec: &ExecutionContext,
     cwd: Option<&Path>,
     tpl: &Tpl,
 ) -> Result<()> {
+    execute_with_context_and_log(ctx, ec, cwd, tpl, None).await
+}
+
+/// Execute the command, appending its output (stdout and stderr) to `log`
+pub async fn execute_with_context_and_log(
+    ctx: &Context,
+    ec: &ExecutionContext,
+    cwd: Option<&Path>,
+    tpl: &Tpl,
+    log: Option<&Path>,
+) -> Result<()> {
+    let cwd = resolve_cwd(ctx, ec, cwd, tpl)?;
+
+    execute_and_log(
+        ctx,
+        &ec.get_args()?,
+        &cwd,
+        &ec.env,
+        &ec.family,
+        &ec.platform,
+        &ec.arch,
+        tpl,
+        log,
+    )
+    .await
+}
+
+/// Working folder of an execution context: `ec.cwd` relative
+/// to `cwd` (the application root folder by default)
+pub fn resolve_cwd(
+    ctx: &Context,
+    ec: &ExecutionContext,
+    cwd: Option<&Path>,
+    tpl: &Tpl,
+) -> Result<PathBuf> {
     let cwd = normalize(tpl.transform(&cwd.unwrap_or(&ctx.app_root_folder).to_string_lossy()))?;
-    let cwd = ec
+    Ok(ec
         .cwd
         .as_ref()
         .map(|folder| {
@@ -66,19 +118,20 @@ pub async fn execute_with_context(
                 cwd.join(folder)
             }
         })
-        .unwrap_or_else(|| cwd.to_path_buf());
+        .unwrap_or(cwd))
+}
 
-    execute(
-        ctx,
-        &ec.get_args()?,
-        &cwd,
-        &ec.env,
-        &ec.family,
-        &ec.platform,
-        &ec.arch,
-        tpl,
-    )
-    .await
+/// `false` if the command is restricted to a different
+/// platform family, platform or architecture
+pub fn is_active(
+    ctx: &Context,
+    family: &Option<PlatformFamily>,
+    platform: &Option<Platform>,
+    arch: &Option<Architecture>,
+) -> bool {
+    (family.is_none() || family.as_ref() == Some(&PlatformFamily::default()))
+        && (platform.is_none() || platform.as_ref() == Some(&ctx.platform))
+        && (arch.is_none() || arch.as_ref() == Some(&ctx.arch))
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -94,17 +147,24 @@ pub async fn execute(
     arch: &Option<Architecture>,
     tpl: &Tpl,
 ) -> Result<()> {
-    let cwd = normalize(cwd)?;
-
-    if family.is_some() && family.as_ref() != Some(&PlatformFamily::default()) {
-        return Ok(());
-    }
+    execute_and_log(ctx, args, cwd, env, family, platform, arch, tpl, None).await
+}
 
-    if platform.is_some() && platform.as_ref() != Some(&ctx.platform) {
-        return Ok(());
-    }
+#[allow(clippy::too_many_arguments)]
+async fn execute_and_log(
+    ctx: &Context,
+    args: &ExecArgs,
+    cwd: &Path,
+    env: &Option<Vec<String>>,
+    family: &Option<PlatformFamily>,
+    platform: &Option<Platform>,
+    arch: &Option<Architecture>,
+    tpl: &Tpl,
+    log: Option<&Path>,
+) -> Result<()> {
+    let cwd = normalize(cwd)?;
 
-    if arch.is_some() && arch.as_ref() != Some(&ctx.arch) {
+    if !is_active(ctx, family, platform, arch) {
         return Ok(());
     }
 
@@ -124,6 +184,15 @@ pub async fn execute(
     let args = argv[1..].to_vec();
 
     let mut proc = duct::cmd(program, args).dir(cwd);
+    if let Some(log) = log {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log)?;
+        proc = proc.stderr_to_stdout().stdout_file(file);
+    } else {
+        proc = proc.stdout_to_log();
+    }
     if let Some(env) = env {
         let defs = get_env_defs(env)?;
         for (k, v) in defs.iter() {
@@ -132,7 +201,7 @@ pub async fn execute(
     }
 
     if let Err(e) = proc.run() {
-        println!("Error executing: {argv:?}");
+        eprintln!("Error executing: {argv:?}");
         Err(e.into())
     } else {
         Ok(())
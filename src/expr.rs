@@ -0,0 +1,523 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+
+/// Value of an `if` expression operand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// Undefined environment variable or unset build state (`flavor`, `variant`)
+    Null,
+    Bool(bool),
+    String(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(value) => *value,
+            Value::String(value) => !value.is_empty(),
+            Value::List(list) => !list.is_empty(),
+        }
+    }
+
+    /// Values are compared by their text representation,
+    /// i.e. `sdk == 'true'` and `env.CI == true` are equivalent
+    fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Null, _) | (_, Value::Null) => false,
+            (Value::List(a), Value::List(b)) => a == b,
+            (a, b) => a.to_string() == b.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::String(value) => f.write_str(value),
+            Value::List(list) => f.write_str(&list.join(",")),
+        }
+    }
+}
+
+/// Variables `if` expressions are evaluated against: build state (`stage`,
+/// `platform`, `arch`, `family`, `target`, `sdk`, `dry_run`, `flavor`,
+/// `variant`), template variables (`NAME`, `VERSION`, ...) and environment
+/// variables (`env.NAME`)
+pub struct Scope<'a> {
+    values: HashMap<&'static str, Value>,
+    tpl: &'a Tpl,
+}
+
+impl<'a> Scope<'a> {
+    pub fn new(stage: &Stage, ctx: &Context, tpl: &'a Tpl, targets: &TargetSet) -> Scope<'a> {
+        let stage = match stage {
+            Stage::Build => "build",
+            Stage::Package => "package",
+            Stage::Deploy => "deploy",
+            Stage::Publish => "publish",
+        };
+        let mut target = targets
+            .iter()
+            .map(|target| target.to_string().to_lowercase())
+            .collect::<Vec<_>>();
+        target.sort();
+        let name = |name: Option<&String>| name.cloned().map_or(Value::Null, Value::String);
+
+        let values = HashMap::from([
+            ("stage", Value::String(stage.to_string())),
+            ("platform", Value::String(ctx.platform.to_string())),
+            ("arch", Value::String(ctx.arch.to_string())),
+            ("family", family(&ctx.platform)),
+            ("target", Value::List(target)),
+            ("sdk", Value::Bool(ctx.sdk)),
+            ("dry_run", Value::Bool(ctx.dry_run)),
+            (
+                "flavor",
+                name(ctx.flavor.as_ref().map(|flavor| &flavor.name)),
+            ),
+            (
+                "variant",
+                name(ctx.variant.as_ref().map(|variant| &variant.name)),
+            ),
+        ]);
+        Scope { values, tpl }
+    }
+
+    fn get(&self, path: &[String]) -> std::result::Result<Value, String> {
+        match path {
+            [env, name] if env == "env" => {
+                Ok(std::env::var(name).map_or(Value::Null, Value::String))
+            }
+            [name] => match self.values.get(name.as_str()) {
+                Some(value) => Ok(value.clone()),
+                None => match self.tpl.map.get(name) {
+                    Some(value) => Ok(Value::String(value.clone())),
+                    None => Err(format!("unknown variable `{name}`")),
+                },
+            },
+            _ => Err(format!("unknown variable `{}`", path.join("."))),
+        }
+    }
+}
+
+/// Family of the target `platform` (not of the host, which may
+/// be building for another platform)
+fn family(platform: &Platform) -> Value {
+    let family = match PlatformFamily::from(platform.clone()) {
+        PlatformFamily::Windows => "windows",
+        PlatformFamily::Unix => "unix",
+    };
+    Value::String(family.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    String(String),
+    Dot,
+    Comma,
+    Open,
+    Close,
+    Not,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(ident) => f.write_str(ident),
+            Token::String(value) => write!(f, "'{value}'"),
+            Token::Dot => f.write_str("."),
+            Token::Comma => f.write_str(","),
+            Token::Open => f.write_str("("),
+            Token::Close => f.write_str(")"),
+            Token::Not => f.write_str("!"),
+            Token::Eq => f.write_str("=="),
+            Token::Ne => f.write_str("!="),
+            Token::And => f.write_str("&&"),
+            Token::Or => f.write_str("||"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Value(Value),
+    Variable(Vec<String>),
+    Call(Box<Node>, String, Vec<Node>),
+    Not(Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Eq(Box<Node>, Box<Node>),
+    Ne(Box<Node>, Box<Node>),
+}
+
+/// Condition of the form `env.CI == 'true' && target.contains('snap') && !sdk`
+/// supporting `==`, `!=`, `&&`, `||`, `!`, parentheses, string literals,
+/// `true`/`false`/`null` and the `contains()`, `starts_with()` and `ends_with()` methods
+#[derive(Debug, Clone)]
+pub struct Expr {
+    text: String,
+    node: Node,
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr> {
+        let tokens = tokenize(text).map_err(|err| invalid(text, &err))?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let node = parser.or().map_err(|err| invalid(text, &err))?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(invalid(text, &format!("unexpected `{token}`")));
+        }
+        Ok(Expr {
+            text: text.to_string(),
+            node,
+        })
+    }
+
+    pub fn eval(&self, scope: &Scope) -> Result<bool> {
+        eval(&self.node, scope)
+            .map(|value| value.is_truthy())
+            .map_err(|err| format!("unable to evaluate `{}`: {err}", self.text).into())
+    }
+}
+
+fn invalid(text: &str, err: &str) -> Error {
+    format!("invalid expression `{text}`: {err}").into()
+}
+
+fn tokenize(text: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '.' => Token::Dot,
+            ',' => Token::Comma,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Ne,
+            '!' => Token::Not,
+            '=' if chars.next_if_eq(&'=').is_some() => Token::Eq,
+            '&' if chars.next_if_eq(&'&').is_some() => Token::And,
+            '|' if chars.next_if_eq(&'|').is_some() => Token::Or,
+            '\'' | '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some(quote) if quote == c => break,
+                        Some(c) => value.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                Token::String(value)
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                Token::Ident(ident)
+            }
+            c => return Err(format!("unexpected character `{c}`")),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next_if(&mut self, token: &Token) -> bool {
+        let matches = self.tokens.get(self.pos) == Some(token);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn expect(&mut self, token: &Token) -> std::result::Result<(), String> {
+        if self.next_if(token) {
+            Ok(())
+        } else {
+            Err(format!("expected `{token}`"))
+        }
+    }
+
+    fn or(&mut self) -> std::result::Result<Node, String> {
+        let mut node = self.and()?;
+        while self.next_if(&Token::Or) {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> std::result::Result<Node, String> {
+        let mut node = self.comparison()?;
+        while self.next_if(&Token::And) {
+            node = Node::And(Box::new(node), Box::new(self.comparison()?));
+        }
+        Ok(node)
+    }
+
+    fn comparison(&mut self) -> std::result::Result<Node, String> {
+        let node = self.unary()?;
+        if self.next_if(&Token::Eq) {
+            Ok(Node::Eq(Box::new(node), Box::new(self.unary()?)))
+        } else if self.next_if(&Token::Ne) {
+            Ok(Node::Ne(Box::new(node), Box::new(self.unary()?)))
+        } else {
+            Ok(node)
+        }
+    }
+
+    fn unary(&mut self) -> std::result::Result<Node, String> {
+        if self.next_if(&Token::Not) {
+            Ok(Node::Not(Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> std::result::Result<Node, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Open) => {
+                let node = self.or()?;
+                self.expect(&Token::Close)?;
+                Ok(node)
+            }
+            Some(Token::String(value)) => Ok(Node::Value(Value::String(value))),
+            Some(Token::Ident(ident)) if ident == "true" => Ok(Node::Value(Value::Bool(true))),
+            Some(Token::Ident(ident)) if ident == "false" => Ok(Node::Value(Value::Bool(false))),
+            Some(Token::Ident(ident)) if ident == "null" => Ok(Node::Value(Value::Null)),
+            Some(Token::Ident(ident)) => {
+                let mut path = vec![ident];
+                while self.next_if(&Token::Dot) {
+                    match self.tokens.get(self.pos).cloned() {
+                        Some(Token::Ident(ident)) => {
+                            self.pos += 1;
+                            path.push(ident);
+                        }
+                        _ => return Err("expected identifier after `.`".to_string()),
+                    }
+                }
+
+                if path.len() > 1 && self.next_if(&Token::Open) {
+                    let method = path.pop().unwrap();
+                    let mut args = Vec::new();
+                    if !self.next_if(&Token::Close) {
+                        loop {
+                            args.push(self.or()?);
+                            if self.next_if(&Token::Close) {
+                                break;
+                            }
+                            self.expect(&Token::Comma)?;
+                        }
+                    }
+                    Ok(Node::Call(Box::new(Node::Variable(path)), method, args))
+                } else {
+                    Ok(Node::Variable(path))
+                }
+            }
+            Some(token) => Err(format!("unexpected `{token}`")),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn eval(node: &Node, scope: &Scope) -> std::result::Result<Value, String> {
+    let value = match node {
+        Node::Value(value) => value.clone(),
+        Node::Variable(path) => scope.get(path)?,
+        Node::Not(node) => Value::Bool(!eval(node, scope)?.is_truthy()),
+        Node::And(a, b) => Value::Bool(eval(a, scope)?.is_truthy() && eval(b, scope)?.is_truthy()),
+        Node::Or(a, b) => Value::Bool(eval(a, scope)?.is_truthy() || eval(b, scope)?.is_truthy()),
+        Node::Eq(a, b) => Value::Bool(eval(a, scope)?.equals(&eval(b, scope)?)),
+        Node::Ne(a, b) => Value::Bool(!eval(a, scope)?.equals(&eval(b, scope)?)),
+        Node::Call(receiver, method, args) => {
+            if !["contains", "starts_with", "ends_with"].contains(&method.as_str()) {
+                return Err(format!("unknown method `{method}()`"));
+            }
+            let receiver = eval(receiver, scope)?;
+            let [arg] = args.as_slice() else {
+                return Err(format!("`{method}()` expects one argument"));
+            };
+            let arg = eval(arg, scope)?.to_string();
+            let result = match (method.as_str(), &receiver) {
+                (_, Value::Null) => false,
+                ("contains", Value::List(list)) => list.contains(&arg),
+                ("contains", value) => value.to_string().contains(&arg),
+                ("starts_with", value) => value.to_string().starts_with(&arg),
+                (_, value) => value.to_string().ends_with(&arg),
+            };
+            Value::Bool(result)
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(tpl: &Tpl) -> Scope<'_> {
+        let values = HashMap::from([
+            ("stage", Value::String("build".to_string())),
+            ("platform", Value::String("linux".to_string())),
+            (
+                "target",
+                Value::List(vec!["archive".to_string(), "snap".to_string()]),
+            ),
+            ("sdk", Value::Bool(false)),
+            ("dry_run", Value::Bool(true)),
+            ("flavor", Value::Null),
+        ]);
+        Scope { values, tpl }
+    }
+
+    fn eval(text: &str) -> Result<bool> {
+        let mut tpl = Tpl::new();
+        tpl.set(&[("NAME", "app")]);
+        Expr::parse(text)?.eval(&scope(&tpl))
+    }
+
+    #[test]
+    fn evaluates_conditions() {
+        std::env::set_var("CARGO_NW_EXPR_CI", "true");
+        assert!(eval("env.CARGO_NW_EXPR_CI == 'true' && target.contains('snap') && !sdk").unwrap());
+        assert!(!eval("env.CARGO_NW_EXPR_CI == 'true' && target.contains('dmg')").unwrap());
+        assert!(eval("platform.starts_with('li') && NAME.ends_with(\"pp\")").unwrap());
+        assert!(eval("stage != 'deploy' && dry_run == 'true'").unwrap());
+    }
+
+    #[test]
+    fn derives_family_from_the_target_platform() {
+        let tpl = Tpl::new();
+        for (platform, windows) in [
+            (Platform::Windows, true),
+            (Platform::Linux, false),
+            (Platform::MacOS, false),
+        ] {
+            let mut scope = scope(&tpl);
+            scope
+                .values
+                .insert("platform", Value::String(platform.to_string()));
+            scope.values.insert("family", family(&platform));
+            let expr = Expr::parse("family == 'windows'").unwrap();
+            assert_eq!(expr.eval(&scope).unwrap(), windows, "{platform}");
+            let expr = Expr::parse("family == 'unix'").unwrap();
+            assert_eq!(expr.eval(&scope).unwrap(), !windows, "{platform}");
+        }
+    }
+
+    #[test]
+    fn applies_precedence() {
+        // `!` binds tighter than `==`: `(!stage) == 'true'`
+        assert!(!eval("!stage == 'true'").unwrap());
+        assert!(eval("!(stage == 'true')").unwrap());
+        assert!(eval("stage == 'x' == false").is_err());
+        // `==` binds tighter than `&&` and `||`
+        assert!(eval("stage == 'build' && platform != 'linux' || sdk == false").unwrap());
+        assert!(!eval("stage == 'build' && (platform != 'linux' || sdk == true)").unwrap());
+        // `&&` binds tighter than `||`
+        assert!(eval("sdk && false || true").unwrap());
+        assert!(eval("true || sdk && false").unwrap());
+        assert!(!eval("(true || sdk) && false").unwrap());
+    }
+
+    #[test]
+    fn treats_undefined_values_as_null() {
+        assert!(eval("env.CARGO_NW_EXPR_UNDEFINED == null").unwrap());
+        assert!(!eval("env.CARGO_NW_EXPR_UNDEFINED").unwrap());
+        assert!(eval("!env.CARGO_NW_EXPR_UNDEFINED").unwrap());
+        assert!(eval("env.CARGO_NW_EXPR_UNDEFINED != ''").unwrap());
+        assert!(!eval("env.CARGO_NW_EXPR_UNDEFINED.contains('x')").unwrap());
+        assert!(eval("flavor == null").unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for text in [
+            "stage == 'build",
+            "stage == \"build",
+            "stage ==",
+            "(stage == 'build'",
+            "stage 'build'",
+            "stage = 'build'",
+            "env.",
+        ] {
+            assert!(Expr::parse(text).is_err(), "{text}");
+        }
+        let err = Expr::parse("stage == 'build").unwrap_err().to_string();
+        assert!(err.contains("unterminated string"), "{err}");
+    }
+
+    #[test]
+    fn rejects_unknown_variables_and_methods() {
+        let err = eval("no_such_variable == 'x'").unwrap_err().to_string();
+        assert!(err.contains("unknown variable `no_such_variable`"), "{err}");
+        let err = eval("stage.value == 'x'").unwrap_err().to_string();
+        assert!(err.contains("unknown variable `stage.value`"), "{err}");
+        let err = eval("target.includes('snap')").unwrap_err().to_string();
+        assert!(err.contains("unknown method `includes()`"), "{err}");
+        let err = eval("target.contains('a', 'b')").unwrap_err().to_string();
+        assert!(err.contains("expects one argument"), "{err}");
+    }
+
+    #[test]
+    fn parses_conditions_when_loading_the_manifest() {
+        let load = |action: &str, item: &str| {
+            let folder =
+                std::env::temp_dir().join(format!("cargo-nw-expr-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&folder).unwrap();
+            let file = folder.join("nw.toml");
+            let text = format!(
+                r#"
+                [application]
+                name = "app"
+                title = "App"
+                version = "0.1.0"
+                organization = "Org"
+
+                [description]
+                short = "short"
+                long = "long"
+
+                [package]
+
+                [nwjs]
+                version = "0.78.1"
+
+                [[action]]
+                if = "{action}"
+                items = [{{ if = "{item}", run = {{ cmd = "true" }} }}]
+                "#
+            );
+            std::fs::write(&file, text).unwrap();
+            let result = async_std::task::block_on(Manifest::load(&file.into()));
+            std::fs::remove_dir_all(&folder).unwrap();
+            result.map(|_| ())
+        };
+        assert!(load("!sdk", "target.contains('snap')").is_ok());
+        let err = load("!sdk", "target.contains('snap'")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid expression"), "{err}");
+        let err = load("sdk &&", "true").unwrap_err().to_string();
+        assert!(err.contains("invalid expression"), "{err}");
+    }
+}
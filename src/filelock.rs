@@ -0,0 +1,87 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::path::Path;
+use async_std::task::sleep;
+use fs2::FileExt;
+use std::io::{ErrorKind, Read, Seek, Write};
+use std::time::Duration;
+
+/// Interval between attempts to acquire a held lock
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Cross-process lock backed by an OS advisory lock (`flock` / `LockFileEx`)
+/// on a `.lock` file kept open for the lifetime of the lock. The lock is
+/// released when dropped or when the owning process exits, so a lock can
+/// never be left stale. The lock file itself is not removed, as another
+/// process may already have it open. The lock file contains the PID of
+/// the process holding the lock, which is reported to waiting processes.
+#[derive(Debug)]
+pub struct FileLock {
+    file: std::fs::File,
+}
+
+impl FileLock {
+    /// Acquire the lock, waiting for other processes holding it.
+    /// `subject` describes the locked resource in log messages.
+    pub async fn acquire(path: &Path, subject: &str) -> Result<FileLock> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|err| format!("unable to create lock `{}`: {err}", path.display()))?;
+
+        let mut waiting = false;
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => {
+                    file.set_len(0)?;
+                    file.rewind()?;
+                    write!(file, "{}", std::process::id())?;
+                    file.flush()?;
+                    return Ok(FileLock { file });
+                }
+                Err(err)
+                    if err.kind() == ErrorKind::WouldBlock
+                        || err.raw_os_error() == fs2::lock_contended_error().raw_os_error() =>
+                {
+                    if !waiting {
+                        waiting = true;
+                        match holder(&mut file) {
+                            Some(pid) => log_info!(
+                                "Blocking",
+                                "waiting for lock on {subject} held by PID {pid}"
+                            ),
+                            None => log_info!("Blocking", "waiting for lock on {subject}"),
+                        }
+                    }
+                }
+                Err(err) => {
+                    return Err(format!("unable to lock `{}`: {err}", path.display()).into());
+                }
+            }
+
+            sleep(LOCK_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// PID of the process holding the lock, as written to the lock file
+/// (`None` if it can not be read, e.g. on Windows where locks are mandatory)
+fn holder(file: &mut std::fs::File) -> Option<u32> {
+    let mut text = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut text).ok()?;
+    text.trim().parse().ok()
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        FileExt::unlock(&self.file).ok();
+    }
+}
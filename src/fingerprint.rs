@@ -0,0 +1,214 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use globset::GlobBuilder;
+use sha2::{Digest, Sha256};
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+/// Folders skipped when collecting inputs and outputs
+/// unless explicitly referenced by a glob
+const IGNORED_FOLDERS: &[&str] = &[".git", "target", "node_modules"];
+
+/// Stored state of a build step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Record {
+    inputs: String,
+    outputs: String,
+}
+
+/// Fingerprint of a build step computed from the command line, environment
+/// and contents of input files. The step is considered up to date if the
+/// fingerprint matches the one stored after the last successful execution
+/// and the declared outputs exist and have not been modified since.
+#[derive(Debug)]
+pub struct Fingerprint {
+    file: PathBuf,
+    folder: PathBuf,
+    force: bool,
+    outputs: Vec<String>,
+    inputs: Option<String>,
+}
+
+impl Fingerprint {
+    /// Create the fingerprint of the build step identified by `key`.
+    /// Globs are relative to `folder`. If there are no `inputs`,
+    /// the step is never considered up to date.
+    pub fn try_new(
+        ctx: &Context,
+        key: &str,
+        folder: &Path,
+        command: &[String],
+        env: &Option<Vec<String>>,
+        inputs: &[String],
+        outputs: &[String],
+    ) -> Result<Fingerprint> {
+        let file = ctx.fingerprint_folder.join(format!("{key}.json"));
+
+        let inputs = if inputs.is_empty() {
+            None
+        } else {
+            let mut hasher = Sha256::new();
+            hasher.update(key.as_bytes());
+            for arg in command.iter() {
+                hasher.update(b"\0");
+                hasher.update(arg.as_bytes());
+            }
+            for def in env.iter().flatten() {
+                hasher.update(b"\0");
+                hasher.update(def.as_bytes());
+            }
+            for relative in collect(folder, inputs)?.0 {
+                hasher.update(b"\0");
+                hasher.update(relative.as_bytes());
+                hasher.update(b"\0");
+                hasher.update(sha256sum(&folder.join(&relative))?.as_bytes());
+            }
+            Some(format!("{:x}", hasher.finalize()))
+        };
+
+        Ok(Fingerprint {
+            file,
+            folder: folder.to_path_buf(),
+            force: ctx.force,
+            outputs: outputs.to_vec(),
+            inputs,
+        })
+    }
+
+    /// `true` if the build step does not need to be executed
+    /// (always `false` if the build was invoked with `--force`)
+    pub async fn is_fresh(&self) -> Result<bool> {
+        let Some(inputs) = &self.inputs else {
+            return Ok(false);
+        };
+        if self.force {
+            return Ok(false);
+        }
+
+        let record: Record = match fs::read_to_string(&self.file).await {
+            Ok(text) => match serde_json::from_str(&text) {
+                Ok(record) => record,
+                Err(_) => return Ok(false),
+            },
+            Err(_) => return Ok(false),
+        };
+
+        if &record.inputs != inputs {
+            return Ok(false);
+        }
+
+        match self.outputs()? {
+            Some(outputs) => Ok(record.outputs == outputs),
+            None => Ok(false),
+        }
+    }
+
+    /// Store the fingerprint after a successful execution of the build step
+    pub async fn store(&self) -> Result<()> {
+        let Some(inputs) = &self.inputs else {
+            return Ok(());
+        };
+
+        let Some(outputs) = self.outputs()? else {
+            log_warn!(
+                "Build",
+                "declared outputs `{}` were not produced",
+                self.outputs.join("`, `")
+            );
+            return Ok(());
+        };
+
+        let record = Record {
+            inputs: inputs.clone(),
+            outputs,
+        };
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&self.file, serde_json::to_string_pretty(&record)?).await?;
+
+        Ok(())
+    }
+
+    /// Hash of output file sizes and modification times; `None` if
+    /// any of the output globs does not match an existing file.
+    fn outputs(&self) -> Result<Option<String>> {
+        let (files, matched) = collect(&self.folder, &self.outputs)?;
+        if matched.iter().any(|matched| !matched) {
+            return Ok(None);
+        }
+
+        let mut hasher = Sha256::new();
+        for relative in files {
+            let metadata = std::fs::metadata(self.folder.join(&relative))?;
+            let modified = metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_nanos())
+                .unwrap_or_default();
+            hasher.update(relative.as_bytes());
+            hasher.update(metadata.len().to_le_bytes());
+            hasher.update(modified.to_le_bytes());
+        }
+        Ok(Some(format!("{:x}", hasher.finalize())))
+    }
+}
+
+/// Sorted list of files in `folder` matching `globs` and, for each
+/// glob, whether it matched at least one file. Each glob is matched
+/// by walking only the folder named by its literal leading components.
+pub fn collect(folder: &Path, globs: &[String]) -> Result<(Vec<String>, Vec<bool>)> {
+    let mut files = Vec::new();
+    let mut matched = vec![false; globs.len()];
+    for (index, glob) in globs.iter().enumerate() {
+        let matcher = GlobBuilder::new(glob)
+            .literal_separator(true)
+            .build()?
+            .compile_matcher();
+
+        let parts = glob.split('/').collect::<Vec<_>>();
+        let literal = parts
+            .iter()
+            .take_while(|part| !part.contains(['*', '?', '[', '{']))
+            .count();
+        let base = folder.join(parts[..literal].join("/"));
+        let max_depth = if parts[literal..].contains(&"**") {
+            usize::MAX
+        } else {
+            parts.len() - literal
+        };
+        let ignored = IGNORED_FOLDERS
+            .iter()
+            .filter(|name| !parts.contains(name))
+            .collect::<Vec<_>>();
+
+        let walker = WalkDir::new(&base)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(|entry| {
+                !(entry.file_type().is_dir()
+                    && entry.depth() > 0
+                    && ignored
+                        .iter()
+                        .any(|name| entry.file_name().to_str() == Some(**name)))
+            });
+        for entry in walker.flatten() {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Ok(relative) = entry.path().strip_prefix(folder) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if matcher.is_match(&relative) {
+                matched[index] = true;
+                files.push(relative);
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+
+    Ok((files, matched))
+}
@@ -100,7 +100,7 @@ impl Images {
     /// MacOS Disk Image (DMG) window background
     pub fn macos_disk_image(&self) -> Vec<String> {
         list(
-            &[self.macos_disk_image.clone()],
+            std::slice::from_ref(&self.macos_disk_image),
             &["macos-disk-image-background.png"],
         )
     }
@@ -130,14 +130,14 @@ impl Images {
 
     pub fn innosetup_wizard_small(&self) -> Vec<String> {
         list(
-            &[self.innosetup_wizard_small.clone()],
+            std::slice::from_ref(&self.innosetup_wizard_small),
             &["innosetup-wizard-small.png"],
         )
     }
 
     pub fn innosetup_wizard_large(&self) -> Vec<String> {
         list(
-            &[self.innosetup_wizard_large.clone()],
+            std::slice::from_ref(&self.innosetup_wizard_large),
             &["innosetup-wizard-large.png"],
         )
     }
@@ -0,0 +1,201 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::path::Path;
+use console::style;
+use regex::Regex;
+
+/// NW runtime details and host toolchain compatibility (`cargo nw info`)
+pub struct Info {
+    ctx: Arc<Context>,
+}
+
+impl Info {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+
+    pub async fn display(&self) -> Result<()> {
+        let ctx = &self.ctx;
+        let release = &ctx.deps.release;
+
+        log_info!(
+            "Application",
+            "{} {}",
+            style(&ctx.manifest.application.title).cyan(),
+            ctx.manifest.application.version
+        );
+        log_info!("Platform", "{}-{}", ctx.platform, ctx.arch);
+
+        if release.is_resolved() {
+            log_info!(
+                "NW",
+                "{} (resolved from `{}`)",
+                style(&release.version).cyan(),
+                release.spec
+            );
+        } else {
+            log_info!("NW", "{}", style(&release.version).cyan());
+        }
+
+        let (chromium, node) = self.components().await;
+        log_info!("Chromium", "{}", chromium.as_deref().unwrap_or("unknown"));
+        log_info!("Node", "{}", node.as_deref().unwrap_or("unknown"));
+
+        let dir = &ctx.deps.dir;
+        let version = release.version.as_str();
+        let nwjs = get_nwjs_meta(&ctx.platform, &ctx.arch, version, dir);
+        let sdk = get_nwjs_sdk_meta(&ctx.platform, &ctx.arch, version, dir);
+        let ffmpeg = get_nwjs_ffmpeg_meta(&ctx.platform, &ctx.arch, version, dir);
+        let ffmpeg_enabled = ctx.manifest.nwjs.ffmpeg.unwrap_or(false);
+        self.display_cached(&nwjs, "NW", true).await;
+        self.display_cached(&sdk, "SDK", ctx.sdk).await;
+        self.display_cached(&ffmpeg, "FFMPEG", ffmpeg_enabled).await;
+
+        self.check_host_node(node.as_deref());
+
+        Ok(())
+    }
+
+    /// Chromium and Node versions from the installed distribution,
+    /// falling back to the lock file or the release index
+    async fn components(&self) -> (Option<String>, Option<String>) {
+        let release = &self.ctx.deps.release;
+        let (chromium, node) = self.installed_components().await;
+        let chromium = chromium.or_else(|| release.chromium.clone());
+        let node = node.or_else(|| release.node.clone());
+        if chromium.is_some() && node.is_some() {
+            return (chromium, node);
+        }
+
+        let location = self
+            .ctx
+            .manifest
+            .nwjs
+            .index
+            .as_deref()
+            .unwrap_or(NWJS_VERSIONS_URL);
+        let folder = &self.ctx.project_root_folder;
+        let index = match VersionIndex::load_offline(location, folder).await {
+            Ok(Some(index)) if index.find(&release.version).is_some() => Some(index),
+            _ => VersionIndex::load(location, folder).await.ok(),
+        };
+
+        let info = index
+            .as_ref()
+            .and_then(|index| index.find(&release.version));
+        (
+            chromium.or_else(|| info.and_then(|info| info.chromium().map(String::from))),
+            node.or_else(|| info.and_then(|info| info.node().map(String::from))),
+        )
+    }
+
+    /// Chromium version from the `nwjs.app` bundle `Info.plist` (MacOS
+    /// distributions) and Node version from `node_version.h` of the
+    /// cached NW headers (see [`NwHeaders`])
+    async fn installed_components(&self) -> (Option<String>, Option<String>) {
+        let ctx = &self.ctx;
+
+        let plist = ctx
+            .deps
+            .nwjs
+            .target()
+            .join("nwjs.app")
+            .join("Contents")
+            .join("Info.plist");
+        let chromium = fs::read_to_string(plist).await.ok().and_then(|text| {
+            Regex::new(r"<key>CFBundleShortVersionString</key>\s*<string>([^<]+)</string>")
+                .ok()?
+                .captures(&text)
+                .map(|c| c[1].trim().to_string())
+        });
+
+        let header = NwHeaders::new(ctx, None)
+            .nodedir()
+            .join("include")
+            .join("node")
+            .join("node_version.h");
+        let node = fs::read_to_string(header).await.ok().and_then(|text| {
+            let define = |name: &str| {
+                Regex::new(&format!(r"#define\s+{name}\s+(\d+)"))
+                    .ok()?
+                    .captures(&text)
+                    .map(|c| c[1].to_string())
+            };
+            Some(format!(
+                "{}.{}.{}",
+                define("NODE_MAJOR_VERSION")?,
+                define("NODE_MINOR_VERSION")?,
+                define("NODE_PATCH_VERSION")?
+            ))
+        });
+
+        (chromium, node)
+    }
+
+    async fn display_cached(&self, meta: &Meta, kind: &str, used: bool) {
+        let folder = self.ctx.deps.dir.join(&meta.folder);
+        let state = if Path::new(&folder).is_dir().await {
+            style("cached").green()
+        } else {
+            style("not cached").yellow()
+        };
+        let used = if used { "" } else { " (not used)" };
+        log_info!("Cache", "{kind:<7} {state}{used} `{}`", folder.display());
+    }
+
+    /// Warn if the host `node` major version differs from the Node
+    /// version bundled with NW, as native modules installed by
+    /// `npm` will fail to load in NW.
+    fn check_host_node(&self, nw_node: Option<&str>) {
+        let uses_npm = self
+            .ctx
+            .manifest
+            .package
+            .build
+            .as_ref()
+            .map(|builds| {
+                builds
+                    .iter()
+                    .any(|build| matches!(build, Build::NPM { .. }))
+            })
+            .unwrap_or(false);
+
+        let host_node = match cmd!("node", "--version").stderr_null().read() {
+            Ok(version) => version.trim().trim_start_matches('v').to_string(),
+            Err(_) => {
+                if uses_npm {
+                    log_warn!("Host Node", "`node` is not available");
+                } else {
+                    log_info!("Host Node", "not available");
+                }
+                return;
+            }
+        };
+        log_info!("Host Node", "{}", host_node);
+
+        if let Some(nw_node) = nw_node {
+            let host_major = major_version(&host_node);
+            let nw_major = major_version(nw_node);
+            if uses_npm && host_major != nw_major {
+                log_warn!(
+                    "Host Node",
+                    "major version {} differs from NW Node {}",
+                    host_major.unwrap_or_default(),
+                    nw_major.unwrap_or_default()
+                );
+                log_warn!(
+                    "Host Node",
+                    "native modules installed by `npm` will not load in NW"
+                );
+            }
+        }
+    }
+}
+
+fn major_version(version: &str) -> Option<u64> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .next()
+        .and_then(|major| major.parse().ok())
+}
@@ -2,7 +2,10 @@ use crate::prelude::*;
 use async_std::path::PathBuf;
 use cfg_if::cfg_if;
 use clap::Subcommand;
-use std::{collections::HashSet, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
 
 // #[derive(Debug, Clone)]
 // pub enum Target {
@@ -11,7 +14,7 @@ use std::{collections::HashSet, str::FromStr};
 //     InnoSetup,
 // }
 
-#[derive(Debug, Clone, Subcommand, Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Subcommand, Serialize, Deserialize, Hash, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum Target {
     All,
@@ -25,9 +28,9 @@ pub enum Target {
     Snap,
 }
 
-impl ToString for Target {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
             Target::All => "all",
             Target::Archive => "Archive",
             #[cfg(any(target_os = "macos", feature = "unix", feature = "multiplatform"))]
@@ -36,8 +39,7 @@ impl ToString for Target {
             Target::InnoSetup => "InnoSetup",
             #[cfg(any(target_os = "linux", feature = "unix", feature = "multiplatform"))]
             Target::Snap => "Snap",
-        }
-        .to_string()
+        })
     }
 }
 
@@ -47,11 +49,11 @@ impl FromStr for Target {
         match s {
             "all" => Ok(Target::All),
             "archive" => Ok(Target::Archive),
-            #[cfg(target_os = "macos")]
+            #[cfg(any(target_os = "macos", feature = "unix", feature = "multiplatform"))]
             "dmg" => Ok(Target::DMG),
-            #[cfg(target_os = "windows")]
+            #[cfg(any(target_os = "windows", feature = "multiplatform"))]
             "innosetup" => Ok(Target::InnoSetup),
-            #[cfg(any(target_os = "linux", feature = "unix"))]
+            #[cfg(any(target_os = "linux", feature = "unix", feature = "multiplatform"))]
             "snap" => Ok(Target::Snap),
             _ => Err(format!("Unsupported target: {s}").into()),
         }
@@ -59,25 +61,37 @@ impl FromStr for Target {
 }
 
 impl Target {
-    pub fn get_all_targets() -> HashSet<Target> {
-        // let list =
-        cfg_if! {
-            if #[cfg(target_os = "macos")] {
-                vec![
-                    Target::Archive,
-                    Target::DMG
-                ].into_iter().collect()
-            } else if #[cfg(target_os = "windows")] {
-                vec![
-                    Target::Archive,
-                    Target::InnoSetup
-                ].into_iter().collect()
-            } else if #[cfg(target_os = "linux")] {
-                vec![
-                    Target::Archive,
-                    Target::Snap
-                ].into_iter().collect()
-            }
+    /// All targets available for `platform` that can be
+    /// produced on the current host
+    pub fn get_all_targets(platform: &Platform) -> HashSet<Target> {
+        let mut targets = vec![Target::Archive];
+        match platform {
+            #[cfg(any(target_os = "macos", feature = "unix", feature = "multiplatform"))]
+            Platform::MacOS => targets.push(Target::DMG),
+            #[cfg(any(target_os = "windows", feature = "multiplatform"))]
+            Platform::Windows => targets.push(Target::InnoSetup),
+            #[cfg(any(target_os = "linux", feature = "unix", feature = "multiplatform"))]
+            Platform::Linux => targets.push(Target::Snap),
+            #[allow(unreachable_patterns)]
+            _ => {}
+        }
+        targets
+            .into_iter()
+            .filter(|target| target.is_host_supported())
+            .collect()
+    }
+
+    /// `false` if the target depends on a toolchain that is
+    /// only available on a specific host operating system
+    pub fn is_host_supported(&self) -> bool {
+        match self {
+            Target::All | Target::Archive => true,
+            #[cfg(any(target_os = "macos", feature = "unix", feature = "multiplatform"))]
+            Target::DMG => cfg!(target_os = "macos"),
+            #[cfg(any(target_os = "windows", feature = "multiplatform"))]
+            Target::InnoSetup => cfg!(target_os = "windows"),
+            #[cfg(any(target_os = "linux", feature = "unix", feature = "multiplatform"))]
+            Target::Snap => cfg!(target_os = "linux"),
         }
     }
 }
@@ -93,13 +107,12 @@ pub enum Channel {
     Devel,
 }
 
-impl ToString for Channel {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
             Channel::Stable => "stable",
             Channel::Devel => "devel",
-        }
-        .to_string()
+        })
     }
 }
 
@@ -125,14 +138,13 @@ pub enum Confinement {
     Devmode,
 }
 
-impl ToString for Confinement {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for Confinement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
             Confinement::Strict => "strict",
             Confinement::Classic => "classic",
             Confinement::Devmode => "devmode",
-        }
-        .to_string()
+        })
     }
 }
 
@@ -151,13 +163,78 @@ impl FromStr for Confinement {
     }
 }
 
+/// Redistributable file produced by an installer
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub target: Target,
+    pub file: PathBuf,
+}
+
+impl Artifact {
+    pub fn new(target: Target, file: PathBuf) -> Artifact {
+        Artifact { target, file }
+    }
+
+    /// Template variables describing this artifact
+    /// (`$ARTIFACT`, `$ARTIFACT_NAME`, `$ARTIFACT_TARGET`)
+    pub fn tpl(&self) -> Tpl {
+        let name = self
+            .file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut tpl = Tpl {
+            map: HashMap::new(),
+        };
+        tpl.set(&[
+            ("ARTIFACT", self.file.to_string_lossy().as_ref()),
+            ("ARTIFACT_NAME", name.as_str()),
+            (
+                "ARTIFACT_TARGET",
+                self.target.to_string().to_lowercase().as_str(),
+            ),
+        ]);
+        tpl
+    }
+}
+
+/// Template variables listing all artifacts (`$ARTIFACTS`) and the
+/// artifacts of each target (`$ARCHIVE_FILE`, `$DMG_FILE`, `$SNAP_FILE`,
+/// `$INNOSETUP_FILE`), separated by spaces
+pub fn artifacts_tpl(artifacts: &[Artifact]) -> Tpl {
+    let mut tpl = Tpl {
+        map: HashMap::new(),
+    };
+    let join = |files: Vec<&Artifact>| {
+        files
+            .iter()
+            .map(|artifact| artifact.file.to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    tpl.set(&[("ARTIFACTS", join(artifacts.iter().collect()).as_str())]);
+    for target in artifacts.iter().map(|artifact| &artifact.target) {
+        let files = artifacts
+            .iter()
+            .filter(|artifact| &artifact.target == target)
+            .collect();
+        let key = format!("{}_FILE", target.to_string().to_uppercase());
+        tpl.set(&[(key.as_str(), join(files).as_str())]);
+    }
+    tpl
+}
+
 #[async_trait]
 pub trait Installer {
     async fn init(&self, targets: &TargetSet) -> Result<()>;
-    async fn create(&self, targets: &TargetSet) -> Result<Vec<PathBuf>>;
+    async fn create(&self, targets: &TargetSet) -> Result<Vec<Artifact>>;
     async fn check(&self, targets: &TargetSet) -> Result<()>;
     fn tpl(&self) -> Tpl;
     fn target_folder(&self) -> PathBuf;
+    /// Executable of the integrated application
+    fn executable(&self) -> PathBuf;
+    /// Artifacts produced by `create` for `targets` (without creating them)
+    fn artifacts(&self, targets: &TargetSet) -> Vec<Artifact>;
 }
 
 pub fn create_installer_tpl(ctx: &Context, target_folder: &PathBuf) -> Tpl {
@@ -179,9 +256,21 @@ pub fn create_installer_tpl(ctx: &Context, target_folder: &PathBuf) -> Tpl {
     tpl
 }
 
-pub fn create_installer(ctx: &Arc<Context>) -> Box<dyn Installer> {
+pub fn create_installer(ctx: &Arc<Context>) -> Result<Box<dyn Installer>> {
     cfg_if! {
-        if #[cfg(feature = "unix")] {
+        if #[cfg(feature = "multiplatform")] {
+            let installer: Box<dyn Installer> = match &ctx.platform {
+                Platform::Linux => {
+                    Box::new(crate::linux::Linux::new(ctx.clone()))
+                },
+                Platform::MacOS => {
+                    Box::new(crate::macos::MacOS::new(ctx.clone()))
+                },
+                Platform::Windows => {
+                    Box::new(crate::windows::Windows::new(ctx.clone()))
+                }
+            };
+        } else if #[cfg(feature = "unix")] {
             let installer: Box<dyn Installer> = match &ctx.platform {
                 Platform::Linux => {
                     Box::new(crate::linux::Linux::new(ctx.clone()))
@@ -190,7 +279,7 @@ pub fn create_installer(ctx: &Arc<Context>) -> Box<dyn Installer> {
                     Box::new(crate::macos::MacOS::new(ctx.clone()))
                 },
                 Platform::Windows => {
-                    panic!("Windows platform can not be used in *nix environment");
+                    return Err("Windows packages require the `multiplatform` feature".into());
                 }
             };
         } else
@@ -204,5 +293,5 @@ pub fn create_installer(ctx: &Arc<Context>) -> Box<dyn Installer> {
         }
     }
 
-    installer
+    Ok(installer)
 }
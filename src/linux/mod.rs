@@ -40,8 +40,8 @@ impl Installer for Linux {
     }
     async fn check(&self, targets: &TargetSet) -> Result<()> {
         if targets.contains(&Target::Snap) {
-            if let Err(err) = cmd("snapcraft", ["--version"]).run() {
-                println!("{err}");
+            if let Err(err) = cmd("snapcraft", ["--version"]).stdout_to_log().run() {
+                log_warn!("Snap", "{err}");
                 return Err(
                     "Unable to run `snapcraft`, please install using `sudo apt install snapcraft`"
                         .into(),
@@ -52,14 +52,22 @@ impl Installer for Linux {
         Ok(())
     }
 
-    async fn create(&self, targets: &TargetSet) -> Result<Vec<PathBuf>> {
+    async fn create(&self, targets: &TargetSet) -> Result<Vec<Artifact>> {
         self.copy_nwjs_folder().await?;
         self.rename_app_binary().await?;
         self.copy_app_data().await?;
         self.copy_icons().await?;
         self.create_desktop_file().await?;
 
-        execute_actions(Stage::Package, &self.ctx, &self.tpl, &self.target_folder).await?;
+        execute_actions(
+            Stage::Package,
+            &self.ctx,
+            &self.tpl,
+            &[],
+            targets,
+            &self.target_folder,
+        )
+        .await?;
 
         let mut files = Vec::new();
         if !self.ctx.dry_run {
@@ -79,7 +87,7 @@ impl Installer for Linux {
             compress_folder(&self.target_folder, &archive_path, level)?;
 
             if !self.ctx.dry_run && targets.contains(&Target::Archive) {
-                files.push(archive_path.clone());
+                files.push(Artifact::new(Target::Archive, archive_path.clone()));
             }
 
             #[cfg(any(target_os = "linux", feature = "unix"))]
@@ -94,7 +102,7 @@ impl Installer for Linux {
                 );
                 snap.create().await?;
                 let snap_file = snap.build().await?;
-                files.push(snap_file);
+                files.push(Artifact::new(Target::Snap, snap_file));
             }
         }
 
@@ -108,6 +116,31 @@ impl Installer for Linux {
     fn target_folder(&self) -> PathBuf {
         self.target_folder.clone()
     }
+
+    fn executable(&self) -> PathBuf {
+        self.target_folder.join(&self.ctx.manifest.application.name)
+    }
+
+    fn artifacts(&self, targets: &TargetSet) -> Vec<Artifact> {
+        let mut files = Vec::new();
+        if targets.contains(&Target::Archive) {
+            let archive_filename = format!("{}.zip", self.ctx.app_snake_name);
+            files.push(Artifact::new(
+                Target::Archive,
+                self.ctx.output_folder.join(archive_filename),
+            ));
+        }
+        #[cfg(any(target_os = "linux", feature = "unix"))]
+        if targets.contains(&Target::Snap) {
+            let application = &self.ctx.manifest.application;
+            let snap_filename = format!("{}_{}_amd64.snap", application.name, application.version);
+            files.push(Artifact::new(
+                Target::Snap,
+                self.ctx.output_folder.join(snap_filename),
+            ));
+        }
+        files
+    }
 }
 
 impl Linux {
@@ -117,6 +150,7 @@ impl Linux {
         options.skip_exist = true;
 
         log_info!("Integrating", "NW binaries");
+        fs::create_dir_all(&self.target_folder).await?;
         dir::copy(
             Path::new(&self.ctx.deps.nwjs.target()),
             &self.target_folder,
@@ -157,6 +191,7 @@ impl Linux {
         )
         .await?;
 
+        self.ctx.copy_flavor_overlay(&self.target_folder).await?;
         self.ctx.update_package_json(&self.target_folder).await?;
 
         Ok(())
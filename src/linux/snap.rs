@@ -26,14 +26,13 @@ impl From<Architecture> for SnapArchitecture {
     }
 }
 
-impl ToString for SnapArchitecture {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for SnapArchitecture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
             SnapArchitecture::amd64 => "amd64",
             SnapArchitecture::i386 => "i386",
             SnapArchitecture::arm64 => "arm64",
-        }
-        .to_string()
+        })
     }
 }
 
@@ -59,11 +58,11 @@ pub enum Plugin {
     Nil,
 }
 
-impl ToString for Plugin {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Plugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Plugin::Dump => "dump".to_string(),
-            Plugin::Nil => "nil".to_string(),
+            Plugin::Dump => write!(f, "dump"),
+            Plugin::Nil => write!(f, "nil"),
         }
     }
 }
@@ -156,7 +155,7 @@ pub struct App {
 
 impl App {
     pub fn new(folder: &str, name: &str, interfaces: Option<HashSet<String>>) -> App {
-        let mut plugs = vec![
+        let mut plugs = [
             "browser-support",
             "network",
             "network-bind",
@@ -294,7 +293,10 @@ impl Snap {
 
         log_info!("Snap", "generating ...");
 
-        cmd!("snapcraft").dir(&self.ctx.build_folder).run()?;
+        cmd!("snapcraft")
+            .dir(&self.ctx.build_folder)
+            .stdout_to_log()
+            .run()?;
 
         let snap_filename = format!(
             "{}_{}_{}.snap",
@@ -0,0 +1,229 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const LOCK_FILE_HEADER: &str = "\
+# This file is automatically generated by cargo-nw.
+# It is not intended for manual editing.
+# Use `cargo nw update` to refresh locked versions.
+";
+
+/// Locked NW release
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedRelease {
+    /// Version specification declared in the manifest
+    pub spec: String,
+    /// Resolved NW version
+    pub version: String,
+    pub chromium: Option<String>,
+    pub node: Option<String>,
+}
+
+/// Locked NW, NW SDK or FFMPEG distribution archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedArchive {
+    pub file: String,
+    pub url: String,
+    pub sha256: String,
+}
+
+/// Locked git dependency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedDependency {
+    pub name: String,
+    pub url: String,
+    pub branch: Option<String>,
+    pub rev: String,
+}
+
+/// Contents of the `nw.lock` file
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lock {
+    pub nwjs: Option<LockedRelease>,
+    #[serde(default, rename = "archive", skip_serializing_if = "Vec::is_empty")]
+    pub archives: Vec<LockedArchive>,
+    #[serde(default, rename = "dependency", skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<LockedDependency>,
+}
+
+/// `nw.lock` file located next to the `nw.toml` manifest.
+///
+/// When `locked` is set (`--locked`), any deviation from the
+/// lock file results in an error and the file is never modified.
+#[derive(Debug)]
+pub struct Lockfile {
+    pub path: PathBuf,
+    pub locked: bool,
+    lock: Mutex<Lock>,
+    stored: Mutex<Lock>,
+}
+
+impl Lockfile {
+    pub async fn load(manifest_folder: &Path, locked: bool) -> Result<Lockfile> {
+        let path = manifest_folder.join("nw.lock");
+        let lock: Lock = if path.exists().await {
+            let text = fs::read_to_string(&path).await?;
+            toml::from_str(&text).map_err(|err| format!("Error loading nw.lock: {err}"))?
+        } else if locked {
+            return Err(format!(
+                "`--locked` requires `{}`; please run `cargo nw update`",
+                path.display()
+            )
+            .into());
+        } else {
+            Lock::default()
+        };
+
+        Ok(Lockfile {
+            path,
+            locked,
+            stored: Mutex::new(lock.clone()),
+            lock: Mutex::new(lock),
+        })
+    }
+
+    /// Returns locked NW release if it was resolved from the same `spec`
+    pub fn release(&self, spec: &str) -> Option<NwRelease> {
+        let lock = self.lock.lock().unwrap();
+        lock.nwjs
+            .as_ref()
+            .filter(|locked| locked.spec == spec)
+            .map(|locked| NwRelease {
+                spec: locked.spec.clone(),
+                version: locked.version.clone(),
+                chromium: locked.chromium.clone(),
+                node: locked.node.clone(),
+            })
+    }
+
+    pub fn set_release(&self, release: &NwRelease) -> Result<()> {
+        let locked = LockedRelease {
+            spec: release.spec.clone(),
+            version: release.version.clone(),
+            chromium: release.chromium.clone(),
+            node: release.node.clone(),
+        };
+
+        let mut lock = self.lock.lock().unwrap();
+        if lock.nwjs.as_ref() != Some(&locked) {
+            if self.locked {
+                return Err(format!(
+                    "NW version `{}` is not locked in `nw.lock`; please run `cargo nw update`",
+                    release.spec
+                )
+                .into());
+            }
+            lock.nwjs = Some(locked);
+            lock.archives.clear();
+        }
+        Ok(())
+    }
+
+    /// Verify the hash of a downloaded NW distribution archive against
+    /// the lock before it is extracted. Unknown archives are recorded; if
+    /// `update` is set, a hash mismatch replaces the locked entry instead
+    /// of producing an error.
+    pub fn verify_archive(&self, meta: &Meta, file: &Path, update: bool) -> Result<()> {
+        log_state!("Lock", "verifying `{}`", meta.file);
+        let sha256 = sha256sum(file)?;
+        log_state_clear();
+
+        let mut lock = self.lock.lock().unwrap();
+        match lock.archives.iter_mut().find(|a| a.file == meta.file) {
+            Some(archive) if archive.sha256 == sha256 => {}
+            Some(archive) if update && !self.locked => {
+                archive.sha256 = sha256;
+                archive.url = meta.url.clone();
+            }
+            Some(_) => {
+                return Err(format!(
+                    "checksum of `{}` does not match `nw.lock`; \
+                    please delete the archive or run `cargo nw update`",
+                    file.display()
+                )
+                .into());
+            }
+            None if self.locked => {
+                return Err(format!(
+                    "`{}` is not locked in `nw.lock`; please run `cargo nw update`",
+                    meta.file
+                )
+                .into());
+            }
+            None => {
+                lock.archives.push(LockedArchive {
+                    file: meta.file.clone(),
+                    url: meta.url.clone(),
+                    sha256,
+                });
+                lock.archives.sort_by(|a, b| a.file.cmp(&b.file));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the locked commit for the git dependency
+    pub fn dependency_rev(&self, git: &Git) -> Option<String> {
+        let lock = self.lock.lock().unwrap();
+        lock.dependencies
+            .iter()
+            .find(|dep| dep.url == git.url && dep.branch == git.branch)
+            .map(|dep| dep.rev.clone())
+    }
+
+    pub fn set_dependency_rev(&self, name: &str, git: &Git, rev: &str) -> Result<()> {
+        let mut lock = self.lock.lock().unwrap();
+        let entry = lock
+            .dependencies
+            .iter_mut()
+            .find(|dep| dep.url == git.url && dep.branch == git.branch);
+
+        match entry {
+            Some(dep) if dep.rev == rev => {}
+            _ if self.locked => {
+                return Err(format!(
+                    "dependency `{name}` is not locked at `{rev}` in `nw.lock`; please run `cargo nw update`"
+                )
+                .into());
+            }
+            Some(dep) => {
+                dep.rev = rev.to_string();
+            }
+            None => {
+                lock.dependencies.push(LockedDependency {
+                    name: name.to_string(),
+                    url: git.url.clone(),
+                    branch: git.branch.clone(),
+                    rev: rev.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the lock file if it has been modified
+    pub async fn store(&self) -> Result<()> {
+        let lock = self.lock.lock().unwrap().clone();
+        if lock == *self.stored.lock().unwrap() && self.path.exists().await {
+            return Ok(());
+        }
+
+        if self.locked {
+            return Err("`nw.lock` needs to be updated but `--locked` was specified".into());
+        }
+
+        let text = format!("{LOCK_FILE_HEADER}\n{}", toml::to_string(&lock)?);
+        fs::write(&self.path, text).await?;
+        log_info!("Lock", "`{}`", self.path.display());
+        *self.stored.lock().unwrap() = lock;
+
+        Ok(())
+    }
+}
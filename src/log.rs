@@ -2,6 +2,7 @@ use console::style;
 use std::fmt;
 
 pub static mut VERBOSE: bool = false;
+pub static mut JSON_MESSAGES: bool = false;
 
 pub fn enable_verbose() {
     unsafe {
@@ -9,31 +10,53 @@ pub fn enable_verbose() {
     }
 }
 
+/// Emit JSON messages on stdout, redirecting log output to stderr
+pub fn enable_json_messages() {
+    unsafe {
+        JSON_MESSAGES = true;
+    }
+}
+
+pub fn json_messages() -> bool {
+    unsafe { JSON_MESSAGES }
+}
+
+/// Print a log line to stdout (stderr if JSON messages are enabled)
+macro_rules! output {
+    ($($t:tt)*) => (
+        if json_messages() {
+            eprint!($($t)*)
+        } else {
+            print!($($t)*)
+        }
+    )
+}
+
 pub mod impls {
     use super::*;
 
     pub fn log_state_impl(source: &str, args: &fmt::Arguments<'_>) {
-        print!("\r\x1b[2K{:>12} {}\r", style(source).green().bold(), args);
+        output!("\r\x1b[2K{:>12} {}\r", style(source).green().bold(), args);
     }
 
     pub fn log_trace_impl(source: &str, args: &fmt::Arguments<'_>) {
         unsafe {
             if VERBOSE {
-                println!("{:>12} {}", style(source).blue().bold(), args);
+                output!("{:>12} {}\n", style(source).blue().bold(), args);
             }
         }
     }
 
     pub fn log_info_impl(source: &str, args: &fmt::Arguments<'_>) {
-        println!("{:>12} {}", style(source).green().bold(), args);
+        output!("{:>12} {}\n", style(source).green().bold(), args);
     }
 
     pub fn log_warn_impl(source: &str, args: &fmt::Arguments<'_>) {
-        println!("{:>12} {}", style(source).yellow().bold(), args);
+        output!("{:>12} {}\n", style(source).yellow().bold(), args);
     }
 
     pub fn stage_impl(args: &fmt::Arguments<'_>) {
-        println!("{:>12} {}", style("Stage").cyan().bold(), args);
+        output!("{:>12} {}\n", style("Stage").cyan().bold(), args);
     }
 }
 
@@ -71,7 +94,11 @@ pub use log_trace;
 pub use log_warn;
 
 pub fn log_state_clear() {
-    print!("\r\x1b[2K");
+    output!("\r\x1b[2K");
+}
+
+pub fn log_newline() {
+    output!("\n");
 }
 
 #[macro_export]
@@ -0,0 +1,46 @@
+use crate::prelude::*;
+use async_std::path::Path;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageOutputFormat};
+use std::io::Cursor;
+
+/// ICNS element types containing PNG data and the corresponding
+/// image dimensions (retina variants use the doubled size)
+const ICNS_ELEMENTS: &[(&[u8; 4], u32)] = &[
+    (b"icp4", 16),
+    (b"icp5", 32),
+    (b"icp6", 64),
+    (b"ic07", 128),
+    (b"ic08", 256),
+    (b"ic09", 512),
+    (b"ic10", 1024),
+    (b"ic11", 32),
+    (b"ic12", 64),
+    (b"ic13", 256),
+    (b"ic14", 512),
+];
+
+/// Write an ICNS file from `src` without relying on `iconutil`,
+/// allowing macOS bundles to be produced on other hosts.
+pub async fn write_icns(src: &DynamicImage, filter: FilterType, icns: &Path) -> Result<()> {
+    let mut elements = Vec::new();
+    for (kind, size) in ICNS_ELEMENTS {
+        let image = src.resize_exact(*size, *size, filter);
+        let mut png = Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, ImageOutputFormat::Png)
+            .map_err(|err| format!("unable to encode {size}x{size} icon: {err}"))?;
+        let png = png.into_inner();
+        elements.extend_from_slice(*kind);
+        elements.extend_from_slice(&(png.len() as u32 + 8).to_be_bytes());
+        elements.extend_from_slice(&png);
+    }
+
+    let mut data = Vec::with_capacity(elements.len() + 8);
+    data.extend_from_slice(b"icns");
+    data.extend_from_slice(&(elements.len() as u32 + 8).to_be_bytes());
+    data.extend_from_slice(&elements);
+    async_std::fs::write(icns, data).await?;
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 pub mod dmg;
+pub mod icns;
 
 use crate::prelude::*;
 use async_std::fs;
@@ -36,7 +37,7 @@ impl Installer for MacOS {
         Ok(())
     }
 
-    async fn create(&self, targets: &TargetSet) -> Result<Vec<PathBuf>> {
+    async fn create(&self, targets: &TargetSet) -> Result<Vec<Artifact>> {
         self.copy_nwjs_bundle().await?;
         self.copy_app_data().await?;
         self.rename_app_bundle(&self.app_contents_folder).await?;
@@ -44,7 +45,15 @@ impl Installer for MacOS {
             .await?;
         self.generate_icons().await?;
 
-        execute_actions(Stage::Package, &self.ctx, &self.tpl, &self.target_folder).await?;
+        execute_actions(
+            Stage::Package,
+            &self.ctx,
+            &self.tpl,
+            &[],
+            targets,
+            &self.target_folder,
+        )
+        .await?;
 
         // if let Some(actions) = &self.ctx.manifest.package.actions {
         //     for action in actions {
@@ -71,7 +80,7 @@ impl Installer for MacOS {
             let target_file = self.ctx.output_folder.join(filename);
             compress_folder(&self.nwjs_root_folder, &target_file, level)?;
 
-            files.push(target_file);
+            files.push(Artifact::new(Target::Archive, target_file));
         }
 
         if !self.ctx.dry_run && targets.contains(&Target::DMG) {
@@ -98,7 +107,7 @@ impl Installer for MacOS {
             );
 
             let dmg_file = dmg.create().await?; // self.create_dmg().await?;
-            files.push(dmg_file);
+            files.push(Artifact::new(Target::DMG, dmg_file));
         }
 
         Ok(files)
@@ -111,6 +120,33 @@ impl Installer for MacOS {
     fn target_folder(&self) -> PathBuf {
         self.target_folder.clone()
     }
+
+    fn executable(&self) -> PathBuf {
+        self.app_contents_folder.join("MacOS").join("nwjs")
+    }
+
+    fn artifacts(&self, targets: &TargetSet) -> Vec<Artifact> {
+        let mut files = Vec::new();
+        if targets.contains(&Target::Archive) {
+            let filename = format!("{}.zip", self.ctx.app_snake_name);
+            files.push(Artifact::new(
+                Target::Archive,
+                self.ctx.output_folder.join(filename),
+            ));
+        }
+        if targets.contains(&Target::DMG) {
+            let application = &self.ctx.manifest.application;
+            let filename = format!(
+                "{}-{}-{}-{}.dmg",
+                application.name, application.version, self.ctx.platform, self.ctx.arch
+            );
+            files.push(Artifact::new(
+                Target::DMG,
+                self.ctx.output_folder.join(filename),
+            ));
+        }
+        files
+    }
 }
 
 impl MacOS {
@@ -184,6 +220,7 @@ impl MacOS {
         )
         .await?;
 
+        self.ctx.copy_flavor_overlay(&self.target_folder).await?;
         self.ctx.update_package_json(&self.target_folder).await?;
 
         Ok(())
@@ -255,6 +292,7 @@ impl MacOS {
 
         cmd!("iconutil", "-c", "icns", "--output", icns, "icns.iconset")
             .dir(&self.ctx.cargo_target_folder)
+            .stdout_to_log()
             .run()?;
 
         std::fs::remove_dir_all(iconset_folder)?;
@@ -277,11 +315,6 @@ impl MacOS {
             );
         }
 
-        let iconset_folder = self.ctx.cargo_target_folder.join("icns.iconset");
-        if !std::path::Path::new(&iconset_folder).exists() {
-            std::fs::create_dir_all(&iconset_folder)?;
-        }
-
         cfg_if! {
             if #[cfg(debug_assertions)] {
                 let resize_filter_type = FilterType::Triangle;
@@ -290,6 +323,15 @@ impl MacOS {
             }
         }
 
+        if cfg!(not(target_os = "macos")) {
+            return icns::write_icns(&src, resize_filter_type, icns).await;
+        }
+
+        let iconset_folder = self.ctx.cargo_target_folder.join("icns.iconset");
+        if !std::path::Path::new(&iconset_folder).exists() {
+            std::fs::create_dir_all(&iconset_folder)?;
+        }
+
         let sizes = vec![512, 256, 128, 64, 32, 16];
         for size in sizes {
             let dest = src.resize(size * 2, size * 2, resize_filter_type);
@@ -303,6 +345,7 @@ impl MacOS {
 
         cmd!("iconutil", "-c", "icns", "--output", icns, "icns.iconset")
             .dir(&self.ctx.cargo_target_folder)
+            .stdout_to_log()
             .run()?;
 
         std::fs::remove_dir_all(iconset_folder)?;
@@ -392,7 +435,10 @@ impl MacOS {
     async fn rename_app_bundle(&self, app_contents_folder: &PathBuf) -> Result<()> {
         log_info!("MacOS", "configuring application bundle");
 
-        let plist_file = app_contents_folder.join("info.plist");
+        let mut plist_file = app_contents_folder.join("Info.plist");
+        if !plist_file.is_file().await {
+            plist_file = app_contents_folder.join("info.plist");
+        }
         self.plist_bundle_rename(
             &plist_file,
             &self.ctx.manifest.application.title,
@@ -4,26 +4,41 @@ use console::style;
 use std::{env, sync::Arc};
 
 pub mod action;
+pub mod addons;
 pub mod archive;
 pub mod builder;
+pub mod cache;
+pub mod cargo;
 pub mod context;
 pub mod copy;
 pub mod deps;
+pub mod download;
 pub mod error;
 pub mod exec;
+pub mod expr;
+pub mod filelock;
+pub mod fingerprint;
 pub mod images;
+pub mod info;
 pub mod init;
 pub mod installer;
+pub mod lock;
 pub mod log;
 pub mod manifest;
+pub mod npm;
+pub mod plan;
 pub mod platform;
 pub mod prelude;
+pub mod report;
 pub mod result;
 pub mod runner;
 pub mod script;
 pub mod signatures;
 pub mod tpl;
 pub mod utils;
+pub mod versions;
+pub mod wasm;
+pub mod watch;
 
 cfg_if! {
     if #[cfg(feature = "multiplatform")] {
@@ -74,10 +89,10 @@ struct Args {
     #[clap(short, long)]
     verbose: bool,
 
-    #[cfg(feature = "unix")]
-    #[clap(short, long)]
-    #[cfg(feature = "unix")]
-    platform: Option<Platform>,
+    /// Target platforms, comma-separated (linux,windows,macos)
+    #[cfg(any(feature = "unix", feature = "multiplatform"))]
+    #[clap(short, long, global = true, value_delimiter = ',')]
+    platform: Vec<Platform>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -96,6 +111,18 @@ enum Action {
         #[clap(short, long, name = "dry-run")]
         dry_run: bool,
 
+        /// Display the build plan without executing anything
+        #[clap(long)]
+        plan: bool,
+
+        /// Require `nw.lock` to be up to date
+        #[clap(long)]
+        locked: bool,
+
+        /// Execute build steps even if they are up to date
+        #[clap(long)]
+        force: bool,
+
         // /// NW version (override the manifest setting)
         // #[clap(short, long)]
         // version : Option<String>,
@@ -105,13 +132,22 @@ enum Action {
         channel: Option<Channel>,
 
         #[cfg(any(target_os = "linux", feature = "unix"))]
-        #[clap(short, long, help = "Snap package confinement (linux only)")]
+        #[clap(long, help = "Snap package confinement (linux only)")]
         #[cfg(any(target_os = "linux", feature = "unix"))]
         confinement: Option<Confinement>,
 
-        /// Target platform architecture (x64,ia32,arm64)
-        #[clap(short, long)]
-        arch: Option<Architecture>,
+        /// Target platform architectures, comma-separated (x64,ia32,arm64)
+        #[clap(short, long, value_delimiter = ',')]
+        arch: Vec<Architecture>,
+
+        /// Message format for build events (human, json)
+        #[clap(long, default_value = "human")]
+        message_format: MessageFormat,
+
+        /// Build only the listed flavors, comma-separated
+        /// (default: the application and all `[[flavor]]` entries)
+        #[clap(long, value_delimiter = ',')]
+        flavor: Vec<String>,
 
         /// Output folder
         #[clap(short, long)]
@@ -127,7 +163,7 @@ enum Action {
     },
     /// Clean intermediate build folders
     Clean {
-        /// Clean downloaded NW redistributables
+        /// Clean NW redistributables used by this project
         #[clap(long)]
         dist: bool,
         /// Clean project dependencies
@@ -137,6 +173,41 @@ enum Action {
         #[clap(long)]
         all: bool,
     },
+    /// Manage NW redistributables cached in `~/.cargo-nw`
+    Cache {
+        #[clap(subcommand)]
+        action: CacheAction,
+    },
+    /// Display NW runtime details and toolchain compatibility
+    Info {
+        /// Use NW SDK edition
+        #[clap(short, long)]
+        sdk: bool,
+
+        /// Override NWJS version
+        #[clap(short, long, name = "nwjs-version")]
+        nwjs_version_override: Option<String>,
+
+        /// Target platform architecture (x64,ia32,arm64)
+        #[clap(short, long)]
+        arch: Option<Architecture>,
+    },
+    /// Manage project dependencies
+    Deps {
+        #[clap(subcommand)]
+        action: DepsAction,
+    },
+    /// Update versions locked in `nw.lock`
+    Update {
+        /// Override NWJS version
+        #[clap(short, long, name = "nwjs-version")]
+        nwjs_version_override: Option<String>,
+
+        /// Target platform architectures, comma-separated (x64,ia32,arm64)
+        /// (default: `package.arch` or the host architecture)
+        #[clap(short, long, value_delimiter = ',')]
+        arch: Vec<Architecture>,
+    },
     /// Create NW application template
     Init {
         /// The name of the project
@@ -167,6 +238,26 @@ enum Action {
         /// Override NWJS version
         #[clap(short, long, name = "nwjs-version")]
         nwjs_version_override: Option<String>,
+
+        /// Execute build steps, rebuild and reload the application on changes
+        #[clap(short, long)]
+        watch: bool,
+
+        /// Run the integrated application from the build folder
+        #[clap(long)]
+        packaged: bool,
+
+        /// Chromium profile folder (default: a temporary per-project profile)
+        #[clap(long)]
+        user_data_dir: Option<String>,
+
+        /// Enable remote debugging (DevTools protocol) on the given port
+        #[clap(long)]
+        remote_debugging_port: Option<u16>,
+
+        /// Arguments passed to the application
+        #[clap(last = true)]
+        args: Vec<String>,
     },
     #[cfg(feature = "test")]
     Test {
@@ -175,6 +266,52 @@ enum Action {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum DepsAction {
+    /// Rebuild a dependency regardless of its build status
+    Rebuild {
+        /// Dependency name
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// List cached NW, SDK and FFMPEG distributions and NW headers
+    List,
+    /// Display disk space used by the cache
+    Size,
+    /// Remove unused distributions and orphaned temp folders
+    Prune {
+        /// Remove distributions not used for the given period (`30d`, `12h`, `2w`)
+        #[clap(long)]
+        older_than: Option<String>,
+        /// Keep only the given number of most recent NW versions
+        #[clap(long)]
+        keep: Option<usize>,
+        /// Display what would be removed without removing anything
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Remove cached distributions
+    Remove {
+        /// NW version to remove
+        version: Option<String>,
+        /// Remove only distributions for this platform
+        #[clap(long)]
+        platform: Option<Platform>,
+        /// Remove only distributions for this architecture
+        #[clap(long)]
+        arch: Option<Architecture>,
+        /// Remove all cached distributions
+        #[clap(long)]
+        all: bool,
+        /// Remove temp folders
+        #[clap(long)]
+        temp: bool,
+    },
+}
+
 pub async fn async_main() -> Result<()> {
     let args = Cmd::parse();
     let Cmd::Args(Args {
@@ -182,17 +319,23 @@ pub async fn async_main() -> Result<()> {
         location,
         verbose,
 
-        #[cfg(feature = "unix")]
-        platform,
+        #[cfg(any(feature = "unix", feature = "multiplatform"))]
+            platform: platforms,
     }) = args;
 
     cfg_if! {
-        if #[cfg(not(feature = "unix"))] {
-            let platform = Platform::default();
+        if #[cfg(not(any(feature = "unix", feature = "multiplatform")))] {
+            let platforms = [Platform::default()];
         } else {
-            let platform = platform.unwrap_or_default();
+            let platforms = if platforms.is_empty() {
+                vec![Platform::default()]
+            } else {
+                platforms
+            };
         }
     }
+    // actions other than `build` operate on the first platform
+    let platform = platforms[0].clone();
 
     match action {
         Action::Build {
@@ -200,7 +343,12 @@ pub async fn async_main() -> Result<()> {
             sdk,
             nwjs_version_override,
             dry_run,
+            plan,
+            locked,
+            force,
+            message_format,
             arch,
+            flavor,
             target,
             default,
             output,
@@ -212,6 +360,11 @@ pub async fn async_main() -> Result<()> {
             if verbose {
                 log::enable_verbose();
             }
+            if message_format == MessageFormat::Json {
+                log::enable_json_messages();
+            }
+            let timestamp = chrono::Utc::now().to_rfc3339();
+            let started = std::time::Instant::now();
 
             let mut targets = TargetSet::new();
             if let Some(target) = target {
@@ -226,10 +379,6 @@ pub async fn async_main() -> Result<()> {
                 targets.insert(Target::All);
             }
 
-            if targets.contains(&Target::All) {
-                targets = Target::get_all_targets();
-            }
-
             cfg_if! {
                 if #[cfg(not(any(target_os = "linux", feature = "unix")))] {
                     let channel = Some(Channel::default());
@@ -243,39 +392,227 @@ pub async fn async_main() -> Result<()> {
                 dry_run,
                 channel,
                 confinement,
+                locked,
+                update: false,
+                force,
+                offline: plan,
+                variant: None,
+                flavor: None,
             };
 
-            let arch = if let Some(arch) = arch {
+            let manifest = Manifest::load(&Manifest::locate(location.clone()).await?).await?;
+            let archs = if !arch.is_empty() {
                 arch
             } else {
-                Architecture::detect()?
+                match manifest.package.arch {
+                    Some(archs) if !archs.is_empty() => archs,
+                    _ => vec![Architecture::detect()?],
+                }
+            };
+
+            let flavors = if !flavor.is_empty() {
+                for name in flavor.iter() {
+                    if !manifest.flavors.iter().flatten().any(|f| &f.name == name) {
+                        return Err(format!("flavor '{name}' not found in nw.toml").into());
+                    }
+                }
+                flavor.into_iter().map(Some).collect::<Vec<_>>()
+            } else {
+                // the application is followed by each `[[flavor]]`
+                let mut flavors = vec![None];
+                flavors.extend(
+                    manifest
+                        .flavors
+                        .iter()
+                        .flatten()
+                        .map(|flavor| Some(flavor.name.clone())),
+                );
+                flavors
             };
 
-            let ctx = Arc::new(Context::create(location, output, platform, arch, options).await?);
+            // the default package is followed by each `[[variant]]`
+            let mut variants = vec![None];
+            variants.extend(
+                manifest
+                    .variants
+                    .iter()
+                    .flatten()
+                    .map(|variant| Some(variant.name.clone())),
+            );
+
+            let mut matrix = Vec::new();
+            for platform in platforms.iter() {
+                for arch in archs.iter() {
+                    for flavor in flavors.iter() {
+                        for variant in variants.iter() {
+                            matrix.push((platform, arch, flavor, variant));
+                        }
+                    }
+                }
+            }
+
+            let label = |platform: &Platform,
+                         arch: &Architecture,
+                         flavor: &Option<String>,
+                         variant: &Option<String>| {
+                let editions = [flavor, variant]
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if editions.is_empty() {
+                    format!("{platform}-{arch}")
+                } else {
+                    format!("{platform}-{arch} ({})", editions.join(", "))
+                }
+            };
 
-            let has_archive = ctx.manifest.package.archive.is_some()
-                || targets.contains(&Target::All)
-                || targets.contains(&Target::Archive);
+            let mut packages = Vec::new();
+            let mut root_folder = None;
+            // template of the first package, which executes the common build steps
+            let mut common_tpl = None;
+            for (index, (platform, arch, flavor, variant)) in matrix.iter().enumerate() {
+                if matrix.len() > 1 {
+                    log_info!(
+                        "Build",
+                        "building `{}` ({}/{})",
+                        label(platform, arch, flavor, variant),
+                        index + 1,
+                        matrix.len()
+                    );
+                }
 
-            if let Some(list) = &ctx.manifest.package.disable {
-                for disable in list.iter() {
-                    match disable {
-                        Target::All => targets.clear(),
-                        _ => {
-                            targets.remove(disable);
+                let ctx = Arc::new(
+                    Context::create(
+                        location.clone(),
+                        output.clone(),
+                        (*platform).clone(),
+                        (*arch).clone(),
+                        Options {
+                            variant: (*variant).clone(),
+                            flavor: (*flavor).clone(),
+                            ..options.clone()
+                        },
+                    )
+                    .await?,
+                );
+
+                let mut targets = match ctx.variant.as_ref().and_then(|v| v.targets.as_ref()) {
+                    Some(list) => list.iter().cloned().collect(),
+                    None => targets.clone(),
+                };
+                if targets.contains(&Target::All) {
+                    targets = Target::get_all_targets(&ctx.platform);
+                }
+                let has_archive = ctx.manifest.package.archive.is_some()
+                    || targets.contains(&Target::All)
+                    || targets.contains(&Target::Archive);
+
+                if let Some(list) = &ctx.manifest.package.disable {
+                    for disable in list.iter() {
+                        match disable {
+                            Target::All => targets.clear(),
+                            _ => {
+                                targets.remove(disable);
+                            }
                         }
                     }
                 }
+
+                if has_archive {
+                    targets.insert(Target::Archive);
+                }
+
+                targets.retain(|target| {
+                    let supported = target.is_host_supported();
+                    if !supported {
+                        log_warn!(
+                            "Build",
+                            "`{target}` target can not be produced on this host, skipping"
+                        );
+                    }
+                    supported
+                });
+
+                root_folder = Some(ctx.root_folder.clone());
+                let installer = create_installer(&ctx)?;
+                let package_common_tpl = common_tpl.clone();
+                if common_tpl.is_none() {
+                    common_tpl = Some(installer.tpl());
+                }
+                if plan {
+                    Plan::new(ctx)
+                        .with_common_tpl(package_common_tpl)
+                        .display(&targets, &installer)
+                        .await?;
+                    continue;
+                }
+                // installer.check().await?;
+                // build steps that are not target-specific are executed only once
+                let build = Arc::new(Builder::new(ctx).with_common_tpl(package_common_tpl));
+                packages.push(build.execute(&targets, &installer).await?);
+            }
+
+            if plan {
+                return Ok(());
+            }
+
+            let files = packages
+                .iter()
+                .flat_map(|package| package.artifacts.iter())
+                .map(|artifact| PathBuf::from(artifact.path.clone()))
+                .collect::<Vec<_>>();
+
+            if matrix.len() > 1 {
+                if let Some(root_folder) = &root_folder {
+                    log_info!(
+                        "Summary",
+                        "{} packages for {}",
+                        files.len(),
+                        matrix
+                            .iter()
+                            .map(|(platform, arch, flavor, variant)| {
+                                label(platform, arch, flavor, variant)
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    log_package_files(root_folder, files.iter())?;
+                }
+            }
+
+            // one report per output folder (flavors are written to their own folders)
+            let duration = started.elapsed().as_secs_f64();
+            let mut reports: Vec<(PathBuf, BuildReport)> = Vec::new();
+            for package in packages {
+                let output_folder = PathBuf::from(package.output_folder.clone());
+                let index = match reports
+                    .iter()
+                    .position(|(folder, _)| *folder == output_folder)
+                {
+                    Some(index) => index,
+                    None => {
+                        let project_folder = root_folder.clone().unwrap_or_else(|| ".".into());
+                        let report = BuildReport::new(&project_folder, timestamp.clone(), duration);
+                        reports.push((output_folder, report));
+                        reports.len() - 1
+                    }
+                };
+                reports[index].1.packages.push(package);
             }
 
-            if has_archive {
-                targets.insert(Target::Archive);
+            let mut report_files = Vec::new();
+            for (output_folder, report) in reports.iter() {
+                if output_folder.is_dir().await {
+                    report_files.push(report.store(output_folder).await?);
+                }
             }
 
-            let installer = create_installer(&ctx);
-            // installer.check().await?;
-            let build = Arc::new(Builder::new(ctx));
-            build.execute(&targets, &installer).await?;
+            emit(&Event::BuildFinished {
+                success: true,
+                report: report_files.first().map(|file| file.as_path().into()),
+                error: None,
+            });
         }
         Action::Clean { all, deps, dist } => {
             let deps = deps || all;
@@ -287,9 +624,9 @@ pub async fn async_main() -> Result<()> {
 
             let ctx = Arc::new(
                 Context::create(
-                    location,
+                    location.clone(),
                     None,
-                    platform,
+                    platform.clone(),
                     Architecture::detect()?,
                     Options::default(),
                 )
@@ -305,6 +642,163 @@ pub async fn async_main() -> Result<()> {
             }
 
             ctx.clean().await?;
+            ctx.clean_build_steps().await?;
+
+            // each flavor and variant has its own build folders
+            let mut flavors = vec![None];
+            flavors.extend(
+                ctx.manifest
+                    .flavors
+                    .iter()
+                    .flatten()
+                    .map(|f| Some(f.name.clone())),
+            );
+            let mut variants = vec![None];
+            variants.extend(
+                ctx.manifest
+                    .variants
+                    .iter()
+                    .flatten()
+                    .map(|v| Some(v.name.clone())),
+            );
+            for flavor in flavors.iter() {
+                for variant in variants.iter() {
+                    if flavor.is_none() && variant.is_none() {
+                        continue;
+                    }
+
+                    let ctx = Context::create(
+                        location.clone(),
+                        None,
+                        platform.clone(),
+                        Architecture::detect()?,
+                        Options {
+                            variant: variant.clone(),
+                            flavor: flavor.clone(),
+                            ..Options::default()
+                        },
+                    )
+                    .await?;
+
+                    if deps {
+                        ctx.clean_dependencies().await?;
+                    }
+
+                    ctx.clean().await?;
+                    ctx.clean_build_steps().await?;
+                }
+            }
+        }
+        Action::Cache { action } => {
+            let cache = Cache::new();
+            match action {
+                CacheAction::List => cache.list().await?,
+                CacheAction::Size => cache.size().await?,
+                CacheAction::Prune {
+                    older_than,
+                    keep,
+                    dry_run,
+                } => {
+                    let options = PruneOptions {
+                        older_than: older_than.as_deref().map(parse_duration).transpose()?,
+                        keep,
+                        dry_run,
+                    };
+                    cache.prune(&options).await?;
+                }
+                CacheAction::Remove {
+                    version,
+                    platform,
+                    arch,
+                    all,
+                    temp,
+                } => {
+                    if version.is_none() && platform.is_none() && arch.is_none() && !all && !temp {
+                        return Err(
+                            "please specify the version to remove or use `--all` or `--temp`"
+                                .into(),
+                        );
+                    }
+                    if all || version.is_some() || platform.is_some() || arch.is_some() {
+                        cache
+                            .remove(version.as_deref(), platform.as_ref(), arch.as_ref())
+                            .await?;
+                    }
+                    if temp {
+                        cache.remove_temp().await?;
+                    }
+                }
+            }
+        }
+        Action::Info {
+            sdk,
+            nwjs_version_override,
+            arch,
+        } => {
+            let arch = if let Some(arch) = arch {
+                arch
+            } else {
+                Architecture::detect()?
+            };
+            let options = Options {
+                sdk,
+                nwjs_version_override,
+                ..Options::default()
+            };
+            let ctx = Arc::new(Context::create(location, None, platform, arch, options).await?);
+            Info::new(ctx).display().await?;
+        }
+        Action::Deps { action } => {
+            let arch = Architecture::detect()?;
+            let ctx = Arc::new(
+                Context::create(location, None, platform, arch, Options::default()).await?,
+            );
+            match action {
+                DepsAction::Rebuild { name } => {
+                    let installer = create_installer(&ctx)?;
+                    let builder = Builder::new(ctx);
+                    builder
+                        .rebuild_dependency(&name, &installer.tpl(), &installer.target_folder())
+                        .await?;
+                }
+            }
+        }
+        Action::Update {
+            nwjs_version_override,
+            arch,
+        } => {
+            let archs = if !arch.is_empty() {
+                arch
+            } else {
+                let manifest = Manifest::load(&Manifest::locate(location.clone()).await?).await?;
+                match manifest.package.arch {
+                    Some(archs) if !archs.is_empty() => archs,
+                    _ => vec![Architecture::detect()?],
+                }
+            };
+
+            // distribution archives are locked for each platform and architecture
+            for platform in platforms.iter() {
+                for arch in archs.iter() {
+                    let options = Options {
+                        nwjs_version_override: nwjs_version_override.clone(),
+                        update: true,
+                        ..Options::default()
+                    };
+                    let ctx = Arc::new(
+                        Context::create(
+                            location.clone(),
+                            None,
+                            platform.clone(),
+                            arch.clone(),
+                            options,
+                        )
+                        .await?,
+                    );
+                    let builder = Builder::new(ctx);
+                    builder.update_lock().await?;
+                }
+            }
         }
         Action::Init {
             name,
@@ -342,13 +836,43 @@ pub async fn async_main() -> Result<()> {
                 Context::create(location, output, platform, arch, Options::default()).await?,
             );
 
-            let installer = create_installer(&ctx);
+            // artifacts are taken from the report of the last build
+            let artifacts = if ctx.output_folder.join(BUILD_REPORT_FILE).exists().await {
+                BuildReport::load(&ctx.output_folder).await?.artifacts()
+            } else {
+                log_warn!(
+                    "Publish",
+                    "no `{BUILD_REPORT_FILE}` found in `{}`, artifacts are not available",
+                    ctx.output_folder.display()
+                );
+                Vec::new()
+            };
+
+            let targets = artifacts
+                .iter()
+                .map(|artifact| artifact.target.clone())
+                .collect::<TargetSet>();
+
+            let installer = create_installer(&ctx)?;
             let target_folder = installer.target_folder();
-            execute_actions(Stage::Publish, &ctx, &installer.tpl(), &target_folder).await?;
+            execute_actions(
+                Stage::Publish,
+                &ctx,
+                &installer.tpl(),
+                &artifacts,
+                &targets,
+                &target_folder,
+            )
+            .await?;
         }
         Action::Run {
             sdk,
             nwjs_version_override,
+            watch,
+            packaged,
+            user_data_dir,
+            remote_debugging_port,
+            args,
         } => {
             let arch = Architecture::detect()?;
             let options = Options {
@@ -357,7 +881,13 @@ pub async fn async_main() -> Result<()> {
                 ..Options::default()
             };
             let ctx = Arc::new(Context::create(location, None, platform, arch, options).await?);
-            let runner = Runner::new(ctx);
+            let runner = Runner::new(ctx).with_options(RunOptions {
+                watch,
+                packaged,
+                user_data_dir,
+                remote_debugging_port,
+                args,
+            });
             runner.run().await?;
         }
         #[cfg(feature = "test")]
@@ -380,9 +910,20 @@ async fn main() -> Result<()> {
     let result = async_main().await;
     match &result {
         // Err(Error::String(s)) => println!("\n{}", style(s).red()),
+        Err(Error::Warning(warn)) if log::json_messages() => {
+            eprintln!("\nWarning: {}\n", style(warn).yellow())
+        }
         Err(Error::Warning(warn)) => {
             println!("\nWarning: {}\n", style(warn).yellow())
         }
+        Err(err) if log::json_messages() => {
+            eprintln!("\n{}\n", style(err).red());
+            emit(&Event::BuildFinished {
+                success: false,
+                report: None,
+                error: Some(err.to_string()),
+            });
+        }
         Err(err) => println!("\n{}\n", style(err).red()),
         Ok(_) => {}
     };
@@ -2,7 +2,7 @@ use crate::prelude::*;
 use async_std::fs::*;
 use async_std::path::{Path, PathBuf};
 use regex::Regex;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
@@ -34,6 +34,16 @@ pub struct Manifest {
     pub snap: Option<Snap>,
     /// Custom overrides of default icon paths
     pub images: Option<Images>,
+    /// NW distribution download settings
+    pub download: Option<Download>,
+    /// `cargo nw run` settings
+    pub run: Option<Run>,
+    /// Additional build variants produced along with the default package
+    #[serde(rename = "variant")]
+    pub variants: Option<Vec<Variant>>,
+    /// White-label flavors of the application
+    #[serde(rename = "flavor")]
+    pub flavors: Option<Vec<Flavor>>,
 
     pub action: Option<Vec<Action>>,
     // pub innosetup : HashMap<String, InnoSetupManifest>,
@@ -55,6 +65,25 @@ pub struct Manifest {
 // }
 
 impl Manifest {
+    /// `true` if the `NPM` build directive installs packages into the package
+    pub fn npm_installs_into_package(&self) -> bool {
+        self.package
+            .build
+            .as_ref()
+            .map(|builds| {
+                builds.iter().any(|build| {
+                    matches!(
+                        build,
+                        Build::NPM {
+                            install: Some(NpmInstall::Package),
+                            ..
+                        }
+                    )
+                })
+            })
+            .unwrap_or(false)
+    }
+
     pub async fn locate(location: Option<String>) -> Result<PathBuf> {
         let cwd = current_dir().await;
 
@@ -131,8 +160,185 @@ impl Manifest {
             return Err(Error::ShortDescriptionIsTooLong);
         }
 
+        let mut names = Vec::new();
+        for variant in self.variants.iter().flatten() {
+            if !regex.is_match(&variant.name) || variant.name.is_empty() {
+                return Err(format!("invalid variant name '{}'", variant.name).into());
+            }
+            if !regex.is_match(&variant.suffix()) {
+                return Err(format!(
+                    "invalid suffix '{}' of variant '{}'",
+                    variant.suffix(),
+                    variant.name
+                )
+                .into());
+            }
+            if names.contains(&&variant.name) {
+                return Err(format!("duplicate variant '{}'", variant.name).into());
+            }
+            names.push(&variant.name);
+        }
+
+        let mut names = Vec::new();
+        for flavor in self.flavors.iter().flatten() {
+            if !regex.is_match(&flavor.name) || flavor.name.is_empty() {
+                return Err(format!("invalid flavor name '{}'", flavor.name).into());
+            }
+            if let Some(name) = flavor
+                .application
+                .as_ref()
+                .and_then(|app| app.name.as_ref())
+            {
+                if !regex.is_match(name) {
+                    return Err(format!(
+                        "invalid application name '{name}' of flavor '{}'",
+                        flavor.name
+                    )
+                    .into());
+                }
+            }
+            if names.contains(&&flavor.name) {
+                return Err(format!("duplicate flavor '{}'", flavor.name).into());
+            }
+            names.push(&flavor.name);
+        }
+
+        for dep in self.dependencies.iter().flatten() {
+            let name = dep.name.as_deref().unwrap_or("dependency");
+            let sources = [dep.git.is_some(), dep.path.is_some(), dep.url.is_some()]
+                .iter()
+                .filter(|source| **source)
+                .count();
+            if sources > 1 {
+                return Err(format!(
+                    "dependency `{name}` can specify only one of `git`, `path` or `url`"
+                )
+                .into());
+            }
+            if dep.sha256.is_some() && dep.url.is_none() {
+                return Err(format!("dependency `{name}` specifies `sha256` without `url`").into());
+            }
+            if let Some(git) = &dep.git {
+                let pins = [&git.branch, &git.tag, &git.rev]
+                    .iter()
+                    .filter(|pin| pin.is_some())
+                    .count();
+                if pins > 1 {
+                    return Err(format!(
+                        "dependency `{name}` can specify only one of `branch`, `tag` or `rev`"
+                    )
+                    .into());
+                }
+                if git.depth == Some(0) {
+                    return Err(
+                        format!("dependency `{name}` has invalid `depth` (must be > 0)").into(),
+                    );
+                }
+            }
+        }
+
+        for action in self.action.iter().flatten() {
+            let conditions = action.items.iter().map(|item| &item.condition);
+            for condition in std::iter::once(&action.condition)
+                .chain(conditions)
+                .flatten()
+            {
+                Expr::parse(condition)?;
+            }
+        }
+
         Ok(())
     }
+
+    /// Apply overrides of the flavor `name` to the manifest
+    pub fn apply_flavor(&mut self, name: &str) -> Result<Flavor> {
+        let flavor = self
+            .flavors
+            .iter()
+            .flatten()
+            .find(|flavor| flavor.name == name)
+            .cloned()
+            .ok_or_else(|| format!("flavor '{name}' not found in nw.toml"))?;
+
+        if let Some(application) = &flavor.application {
+            let target = &mut self.application;
+            for (value, field) in [
+                (&application.name, &mut target.name),
+                (&application.version, &mut target.version),
+                (&application.title, &mut target.title),
+                (&application.organization, &mut target.organization),
+            ] {
+                if let Some(value) = value {
+                    *field = value.clone();
+                }
+            }
+            for (value, field) in [
+                (&application.authors, &mut target.authors),
+                (&application.copyright, &mut target.copyright),
+                (&application.trademarks, &mut target.trademarks),
+                (&application.license, &mut target.license),
+                (&application.eula, &mut target.eula),
+                (&application.url, &mut target.url),
+            ] {
+                if value.is_some() {
+                    *field = value.clone();
+                }
+            }
+        }
+
+        if flavor.images.is_some() {
+            self.images = flavor.images.clone();
+        }
+        if flavor.resources.is_some() {
+            self.package.resources = flavor.resources.clone();
+        }
+
+        if let Some(windows) = &flavor.windows {
+            let Some(target) = self.windows.as_mut() else {
+                return Err(format!(
+                    "flavor '{name}' overrides `windows` settings, but nw.toml has no [windows] section"
+                )
+                .into());
+            };
+            if let Some(uuid) = &windows.uuid {
+                target.uuid = uuid.clone();
+            }
+            if let Some(group) = &windows.group {
+                target.group = group.clone();
+            }
+            if windows.executable.is_some() {
+                target.executable = windows.executable.clone();
+            }
+        }
+
+        Ok(flavor)
+    }
+
+    /// Apply overrides of the variant `name` to the manifest
+    pub fn apply_variant(&mut self, name: &str) -> Result<Variant> {
+        let variant = self
+            .variants
+            .iter()
+            .flatten()
+            .find(|variant| variant.name == name)
+            .cloned()
+            .ok_or_else(|| format!("variant '{name}' not found in nw.toml"))?;
+
+        if variant.sdk.is_some() {
+            self.nwjs.sdk = variant.sdk;
+        }
+        if variant.ffmpeg.is_some() {
+            self.nwjs.ffmpeg = variant.ffmpeg;
+        }
+        if variant.include.is_some() {
+            self.package.include = variant.include.clone();
+        }
+        if variant.exclude.is_some() {
+            self.package.exclude = variant.exclude.clone();
+        }
+
+        Ok(variant)
+    }
 }
 
 /// Application section of the nw.toml manifest
@@ -183,6 +389,12 @@ pub struct ExecutionContext {
     pub arch: Option<Architecture>,
     pub family: Option<PlatformFamily>,
     pub env: Option<Vec<String>>,
+    /// Input file globs used to detect whether the `custom` build
+    /// step needs to be executed. If not specified, the step is
+    /// always executed.
+    pub inputs: Option<Vec<String>>,
+    /// Output file globs that must exist for the `custom` build step to be skipped.
+    pub outputs: Option<Vec<String>>,
 }
 
 impl ExecutionContext {
@@ -251,46 +463,195 @@ pub enum Execute {
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub enum Build {
-    /// Run `wasmpack` before the integration.
+    /// Run `wasmpack` before the integration. Multiple crates
+    /// can be built using multiple `WASM` entries.
     WASM {
         /// Runs `cargo clean` before the build.
         clean: Option<bool>,
         /// Deletes the `target` folder before the build.
         purge: Option<bool>,
-        /// Enable `wasmpack` development build.
+        /// Crate folder relative to the application folder
+        /// (default: the application folder).
+        path: Option<String>,
+        /// `wasm-pack` target: `web` (default), `bundler`,
+        /// `nodejs`, `no-modules` or `deno`.
+        target: Option<WasmTarget>,
+        /// Build profile: `dev`, `release` (default) or `profiling`.
+        profile: Option<WasmProfile>,
+        /// Enable `wasmpack` development build
+        /// (same as `profile = "dev"`).
         dev: Option<bool>,
-        /// WASM package name
+        /// WASM package name (default: the application name, or
+        /// the crate name if `path` is specified)
         name: Option<String>,
         /// Specify a custom output directory (default `app/wasm`)
-        /// when running the build command.
+        /// relative to the application folder.
         outdir: Option<String>,
-        /// Shell arguments for the build command.
-        args: Option<String>,
+        /// Run `wasm-opt` on the generated WASM file with the
+        /// given optimization level (`0`-`4`, `s` or `z`).
+        #[serde(rename = "wasm-opt")]
+        wasm_opt: Option<String>,
+        /// Additional arguments for the build command, as a
+        /// space-separated string or a list of arguments.
+        args: Option<BuildArgs>,
         /// Environment variables for the build command in the
         /// form of "VAR=VALUE" per entry.
         env: Option<Vec<String>>,
+        /// Input file globs (relative to the application folder) used to detect
+        /// whether the step needs to be executed (default: `Cargo.toml`, `Cargo.lock`,
+        /// `build.rs`, `src/**` in the crate folder).
+        inputs: Option<Vec<String>>,
+        /// Output file globs (relative to the application folder) that must exist
+        /// for the step to be skipped (default: `<outdir>/<name>*`).
+        outputs: Option<Vec<String>>,
     },
-    /// Run `npm` before the integration
+    /// Run `npm`, `yarn` or `pnpm` before the integration
     NPM {
+        /// Package manager: `npm`, `yarn` or `pnpm`. If not specified,
+        /// the package manager is detected from the lock file present
+        /// in the application folder (`npm` if there is no lock file).
+        manager: Option<PackageManager>,
         /// Deletes `node_modules` folder before running `npm`.
         clean: Option<bool>,
-        /// Deletes `package-lock.json` folder before running `npm`.
+        /// Deletes the package lock file before running `npm`.
         #[serde(rename = "clean-package-lock")]
         clean_package_lock: Option<bool>,
         /// Enables `npm` development build. By default the build
         /// process will include `--omit dev` argument, causing
         /// NPM to produce a release build.
         dev: Option<bool>,
+        /// Install exactly the versions in the lock file (`npm ci`,
+        /// `--frozen-lockfile`, `--immutable`), failing if the lock file
+        /// is out of date. Enabled by default if there is a lock file
+        /// and always enabled with `--locked`.
+        frozen: Option<bool>,
+        /// Install only the listed workspace packages
+        /// (`--workspace`, `yarn workspaces focus`, `--filter`).
+        workspaces: Option<Vec<String>>,
+        /// Install location: `source` (the application folder, default) or
+        /// `package`. With `package`, `package.json` and the lock file are
+        /// copied to a staging folder where production packages are installed
+        /// and merged into the application package. The source `node_modules`
+        /// folder is left untouched and excluded from the package.
+        install: Option<NpmInstall>,
         /// Additional command line arguments passed to `npm`.
         args: Option<String>,
         /// Environment variables for the npm build command.
         env: Option<Vec<String>>,
+        /// Input file globs (relative to the application folder) used to detect
+        /// whether the step needs to be executed (default: `package.json`, the lock file and package manager configuration files).
+        inputs: Option<Vec<String>>,
+        /// Output file globs (relative to the application folder) that must exist
+        /// for the step to be skipped (default: `node_modules/.*` state files).
+        outputs: Option<Vec<String>>,
+    },
+    /// Run `cargo build` and integrate the produced executables
+    /// (for example, native helper processes started by the application).
+    /// The build is executed in the project folder.
+    Cargo {
+        /// Package to build (`--package`)
+        package: Option<String>,
+        /// Binaries to build and integrate (`--bin`). If not specified,
+        /// all binaries of the package are integrated.
+        bin: Option<Vec<String>>,
+        /// Cargo build profile (default: `release`)
+        profile: Option<String>,
+        /// Cargo features to enable
+        features: Option<Vec<String>>,
+        /// Rust target triple (default: the triple matching
+        /// the target platform and architecture)
+        target: Option<String>,
+        /// Folder relative to the application root in the package
+        /// where executables are copied (default: the root folder)
+        #[serde(rename = "copy-to")]
+        copy_to: Option<String>,
+        /// Environment variables for the cargo build command.
+        env: Option<Vec<String>>,
+        /// Input file globs (relative to the project folder) used to detect
+        /// whether the step needs to be executed (default: `Cargo.toml` files, `Cargo.lock` and `.rs` files).
+        inputs: Option<Vec<String>>,
+        /// Output file globs (relative to the project folder) that must exist
+        /// for the step to be skipped (default: none).
+        outputs: Option<Vec<String>>,
+    },
+    /// Rebuild native Node addons located in `node_modules` against
+    /// the NW headers using `nw-gyp`. Should follow the `NPM` directive.
+    Rebuild {
+        /// Names of the packages to rebuild (default: all packages
+        /// containing `binding.gyp`).
+        modules: Option<Vec<String>>,
+        /// Location of `node_modules`, matching the `install` option of
+        /// the `NPM` directive: `source` (default) or `package`.
+        install: Option<NpmInstall>,
+        /// Source of NW headers: a URL or a local folder with the same
+        /// layout as the NW download site (default: `https://dl.nwjs.io`).
+        headers: Option<String>,
+        /// Command used to rebuild addons (default: `nw-gyp`).
+        tool: Option<String>,
+        /// Additional arguments for the rebuild command, as a
+        /// space-separated string or a list of arguments.
+        args: Option<BuildArgs>,
+        /// Environment variables for the rebuild command.
+        env: Option<Vec<String>>,
+        /// Input file globs (relative to the folder containing `node_modules`)
+        /// used to detect whether the step needs to be executed
+        /// (default: `package.json` and `binding.gyp` of each addon).
+        inputs: Option<Vec<String>>,
+        /// Output file globs that must exist for the step to be
+        /// skipped (default: `build/Release/*.node` of each addon).
+        outputs: Option<Vec<String>>,
     },
     /// Run a custom script/command before the integration
     #[serde(rename = "custom")]
     Custom(ExecutionContext),
 }
 
+impl Build {
+    /// Name of the build directive
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Build::WASM { .. } => "wasm",
+            Build::NPM { .. } => "npm",
+            Build::Cargo { .. } => "cargo",
+            Build::Rebuild { .. } => "rebuild",
+            Build::Custom(_) => "custom",
+        }
+    }
+
+    /// `true` if the build step produces platform, architecture, flavor or
+    /// variant-specific output and must be executed for each package built.
+    /// Custom steps are target-specific if their command line, environment,
+    /// working folder, inputs or outputs resolved with `tpl` differ from
+    /// those resolved with `common_tpl` (the template of the package that
+    /// executed the common build steps).
+    pub fn is_target_specific(&self, tpl: &Tpl, common_tpl: &Tpl) -> Result<bool> {
+        let specific = match self {
+            Build::WASM { .. } => false,
+            Build::NPM { install, .. } => install == &Some(NpmInstall::Package),
+            Build::Cargo { .. } | Build::Rebuild { .. } => true,
+            Build::Custom(ec) => {
+                let resolve = |tpl: &Tpl| -> Result<Vec<String>> {
+                    let mut resolved = ec.get_args()?.get(tpl);
+                    resolved.extend(
+                        ec.cwd
+                            .iter()
+                            .chain(ec.env.iter().flatten())
+                            .chain(ec.inputs.iter().flatten())
+                            .chain(ec.outputs.iter().flatten())
+                            .map(|text| tpl.transform(text)),
+                    );
+                    Ok(resolved)
+                };
+                ec.arch.is_some()
+                    || ec.platform.is_some()
+                    || ec.family.is_some()
+                    || resolve(tpl)? != resolve(common_tpl)?
+            }
+        };
+        Ok(specific)
+    }
+}
+
 /// Package directives
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields, rename_all = "kebab-case")]
@@ -305,6 +666,9 @@ pub struct Package {
     pub build: Option<Vec<Build>>,
     /// Forces cargo-nw to always generate an Archive build.
     pub archive: Option<Archive>,
+    /// Architectures built when `--arch` is not specified
+    /// (default: the host architecture).
+    pub arch: Option<Vec<Architecture>>,
     /// Disables build types except archive (`build all` will result in archive only)
     /// This can be useful for utility projects that do not require interactive installation.
     pub disable: Option<Vec<Target>>,
@@ -345,6 +709,83 @@ pub struct Package {
     pub update_package_json: Option<bool>,
 }
 
+/// Build variant (`[[variant]]` section) producing an additional
+/// package with its own artifact names and build folders
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Variant {
+    /// Variant name
+    pub name: String,
+    /// Suffix appended to the application name in artifact
+    /// and folder names (default: `-<name>`)
+    pub suffix: Option<String>,
+    /// Use NW SDK edition (overrides `nwjs.sdk`)
+    pub sdk: Option<bool>,
+    /// Integrate FFMPEG libraries (overrides `nwjs.ffmpeg`)
+    pub ffmpeg: Option<bool>,
+    /// Inclusion globs (replace `package.include`)
+    pub include: Option<Vec<CopyFilter>>,
+    /// Exclusion globs (replace `package.exclude`)
+    pub exclude: Option<Vec<CopyFilter>>,
+    /// Targets produced for this variant (default: targets
+    /// specified on the command line)
+    pub targets: Option<Vec<Target>>,
+}
+
+impl Variant {
+    pub fn suffix(&self) -> String {
+        self.suffix
+            .clone()
+            .unwrap_or_else(|| format!("-{}", self.name))
+    }
+}
+
+/// White-label flavor (`[[flavor]]` section) producing a differently
+/// branded application with its own build and output folders
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Flavor {
+    /// Flavor name (`cargo nw build --flavor <name>`)
+    pub name: String,
+    /// Overrides of the `[application]` section
+    pub application: Option<FlavorApplication>,
+    /// Overrides of the `[images]` section
+    pub images: Option<Images>,
+    /// Setup resources folder relative to the manifest
+    /// file (overrides `package.resources`)
+    pub resources: Option<String>,
+    /// Overrides of the `[windows]` section
+    pub windows: Option<FlavorWindows>,
+    /// Folder relative to the manifest file whose contents are copied
+    /// into the application package, replacing existing files
+    pub overlay: Option<String>,
+}
+
+/// Flavor overrides of the `[application]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlavorApplication {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub title: Option<String>,
+    pub authors: Option<String>,
+    pub organization: Option<String>,
+    pub copyright: Option<String>,
+    pub trademarks: Option<String>,
+    pub license: Option<String>,
+    pub eula: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Flavor overrides of the `[windows]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlavorWindows {
+    pub uuid: Option<String>,
+    pub group: Option<String>,
+    pub executable: Option<String>,
+}
+
 /// Copy filter used in `package.include` and `package.exclude` sections
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -393,6 +834,26 @@ pub struct Git {
     pub url: String,
     /// Repository branch
     pub branch: Option<String>,
+    /// Tag the dependency is pinned to
+    pub tag: Option<String>,
+    /// Commit the dependency is pinned to (must be a full
+    /// commit hash when used with `depth`)
+    pub rev: Option<String>,
+    /// Clone and update submodules (default: false)
+    pub submodules: Option<bool>,
+    /// Create a shallow clone with the given history depth
+    pub depth: Option<u32>,
+}
+
+impl Git {
+    /// Name of the repository folder (the last url component)
+    pub fn repository(&self) -> &str {
+        self.url
+            .trim_end_matches('/')
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or(&self.url)
+    }
 }
 
 /// Dependency section
@@ -405,10 +866,40 @@ pub struct Dependency {
     pub arch: Option<Vec<Architecture>>,
     /// Git url of the dependency repository
     pub git: Option<Git>,
+    /// Local checkout of the dependency (relative to the project folder)
+    pub path: Option<String>,
+    /// Url or local path of a prebuilt archive. The url can contain
+    /// template variables (`$PLATFORM`, `$ARCH`, ...) and environment
+    /// variables (e.g. `$DEPS_URL/tool-$PLATFORM-$ARCH.zip`).
+    pub url: Option<String>,
+    /// SHA-256 checksum of the `url` archive
+    pub sha256: Option<Checksum>,
+    #[serde(default)]
     pub run: Vec<ExecutionContext>,
+    #[serde(default)]
     pub copy: Vec<Copy>,
 }
 
+/// Checksum of a downloaded file, either a single value or
+/// a table of values keyed by `<platform>-<arch>` (e.g. `linux-x64`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Checksum {
+    Any(String),
+    Target(HashMap<String, String>),
+}
+
+impl Checksum {
+    pub fn get(&self, platform: &Platform, arch: &Architecture) -> Option<&str> {
+        match self {
+            Checksum::Any(checksum) => Some(checksum),
+            Checksum::Target(checksums) => checksums
+                .get(&format!("{platform}-{arch}"))
+                .map(String::as_str),
+        }
+    }
+}
+
 /// NW Directives
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename = "node-webkit", deny_unknown_fields)]
@@ -417,6 +908,11 @@ pub struct NWJS {
     /// NW version. This version must be downloadable
     /// from https://nwjs.io/downloads
     ///
+    /// In addition to exact versions (`0.70.1`), the version can be
+    /// specified as `latest`, `stable`, `lts`, a partial version (`0.80`)
+    /// or a semver requirement (`^0.78`), in which case it is resolved
+    /// against the NW release index (see `index`).
+    ///
     /// WARNING: If using FFMPEG builds, the available FFMPEG version
     /// must match the NW version. FFMPEG downloads are available
     /// at: https://github.com/nwjs-ffmpeg-prebuilt/nwjs-ffmpeg-prebuilt/releases/
@@ -438,6 +934,10 @@ pub struct NWJS {
     /// ```
     /// Be aware that SDK builds allow users access to your application environment
     pub sdk: Option<bool>,
+    /// Location of the NW release index used to resolve non-exact versions.
+    /// Can be an url or a path relative to the manifest file
+    /// (default: `https://nwjs.io/versions.json`).
+    pub index: Option<String>,
 }
 
 impl NWJS {
@@ -454,6 +954,44 @@ impl NWJS {
     }
 }
 
+/// Settings for downloading NW distributions.
+///
+/// Proxies configured via `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY`
+/// and `NO_PROXY` environment variables are used unless `proxy`
+/// is specified.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Download {
+    /// Number of retries for failed downloads (default: 5)
+    pub retries: Option<u32>,
+    /// Initial delay in seconds between retries, doubled
+    /// after each failed attempt (default: 2)
+    pub backoff: Option<u64>,
+    /// Connection timeout in seconds (default: 30)
+    pub connect_timeout: Option<u64>,
+    /// Timeout in seconds for receiving data (default: 60)
+    pub read_timeout: Option<u64>,
+    /// Proxy url used for all downloads
+    pub proxy: Option<String>,
+    /// Comma-separated list of hosts excluded from the proxy
+    /// (default: `NO_PROXY` environment variable)
+    pub no_proxy: Option<String>,
+}
+
+/// Settings used when running the application with `cargo nw run`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Run {
+    /// Environment variables (`KEY=VALUE`)
+    pub env: Option<Vec<String>>,
+    /// Arguments passed to the application
+    /// (followed by arguments given after `--`)
+    pub args: Option<Vec<String>>,
+    /// Chromium profile folder, relative to the project
+    /// (default: a temporary per-project profile)
+    pub user_data_dir: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct InnoSetup {
@@ -486,6 +1024,10 @@ pub struct Windows {
     /// application executable. Additional information can be
     /// found here: https://learn.microsoft.com/en-us/windows/win32/menurc/string-str
     pub resources: Option<Vec<WindowsResourceString>>,
+    /// Update the application executable icon, version and resource
+    /// strings (default `true`). On non-Windows hosts this requires
+    /// `rcedit`; set to `false` to retain the default NW resources.
+    pub update_resources: Option<bool>,
 }
 
 /// Windows resource strings: https://learn.microsoft.com/en-us/windows/win32/menurc/string-str
@@ -652,15 +1194,14 @@ impl From<Algorithm> for zip::CompressionMethod {
     }
 }
 
-impl ToString for Algorithm {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
             Algorithm::STORE => "STORE",
             Algorithm::BZIP2 => "BZIP2",
             Algorithm::DEFLATE => "DEFLATE",
             Algorithm::ZSTD => "ZSTD",
-        }
-        .into()
+        })
     }
 }
 
@@ -0,0 +1,274 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use fs_extra::dir;
+use std::fmt;
+
+/// Configuration files copied along with `package.json`
+/// when installing outside of the application folder
+const CONFIG_FILES: &[&str] = &[".npmrc", ".yarnrc", ".yarnrc.yml", ".pnpmfile.cjs"];
+/// Yarn 2+ folders containing the yarn release and plugins
+const CONFIG_FOLDERS: &[&str] = &[".yarn/releases", ".yarn/plugins"];
+
+/// Location where the `NPM` build directive installs packages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NpmInstall {
+    /// Install into the application (source) folder
+    #[default]
+    Source,
+    /// Install production packages into the application package,
+    /// leaving the source `node_modules` folder untouched
+    Package,
+}
+
+/// Node package manager used by the `NPM` build directive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Npm,
+    Yarn,
+    Pnpm,
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageManager::Npm => write!(f, "npm"),
+            PackageManager::Yarn => write!(f, "yarn"),
+            PackageManager::Pnpm => write!(f, "pnpm"),
+        }
+    }
+}
+
+/// Options of the package installation
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// Install development dependencies
+    pub dev: bool,
+    /// Fail if the lock file needs to be updated
+    pub frozen: bool,
+    /// Install only the listed workspace packages
+    pub workspaces: Vec<String>,
+}
+
+impl PackageManager {
+    /// Detect the package manager from the lock file present
+    /// in the folder (`npm` if there is no lock file)
+    pub async fn detect(folder: &Path) -> PackageManager {
+        for manager in [
+            PackageManager::Pnpm,
+            PackageManager::Yarn,
+            PackageManager::Npm,
+        ] {
+            if manager.find_lockfile(folder).await.is_some() {
+                return manager;
+            }
+        }
+        PackageManager::Npm
+    }
+
+    pub fn lockfiles(&self) -> &'static [&'static str] {
+        match self {
+            PackageManager::Npm => &["package-lock.json", "npm-shrinkwrap.json"],
+            PackageManager::Yarn => &["yarn.lock"],
+            PackageManager::Pnpm => &["pnpm-lock.yaml"],
+        }
+    }
+
+    pub async fn find_lockfile(&self, folder: &Path) -> Option<PathBuf> {
+        for lockfile in self.lockfiles() {
+            let file = folder.join(lockfile);
+            if file.is_file().await {
+                return Some(file);
+            }
+        }
+        None
+    }
+
+    /// Files affecting the package installation: `package.json`,
+    /// lock files and package manager configuration files
+    pub fn inputs(&self) -> Vec<String> {
+        let mut files = vec!["package.json".to_string()];
+        files.extend(self.lockfiles().iter().map(|file| file.to_string()));
+        files.extend(CONFIG_FILES.iter().map(|file| file.to_string()));
+        files
+    }
+
+    /// Copy `package.json`, the lock file and package manager
+    /// configuration files from `folder` to the `staging` folder.
+    /// Packages previously installed in the `staging` folder are
+    /// retained so that an unchanged installation can be skipped.
+    pub async fn stage(&self, folder: &Path, staging: &Path) -> Result<()> {
+        let package_json = folder.join("package.json");
+        if !package_json.is_file().await {
+            return Err(format!("unable to locate `{}`", package_json.display()).into());
+        }
+
+        fs::create_dir_all(staging).await?;
+        fs::copy(&package_json, staging.join("package.json")).await?;
+
+        // remove files that no longer exist in the source folder
+        for file in self.lockfiles().iter().chain(CONFIG_FILES.iter()) {
+            let file = staging.join(file);
+            if file.is_file().await && !folder.join(file.file_name().unwrap()).is_file().await {
+                fs::remove_file(&file).await?;
+            }
+        }
+
+        let mut files = CONFIG_FILES
+            .iter()
+            .map(|file| folder.join(file))
+            .collect::<Vec<_>>();
+        files.extend(self.find_lockfile(folder).await);
+        for file in files {
+            if file.is_file().await {
+                fs::copy(&file, staging.join(file.file_name().unwrap())).await?;
+            }
+        }
+
+        for config_folder in CONFIG_FOLDERS {
+            let src = folder.join(config_folder);
+            if src.is_dir().await {
+                let dest = staging.join(config_folder);
+                fs::create_dir_all(&dest).await?;
+                let mut options = dir::CopyOptions::new();
+                options.content_only = true;
+                options.overwrite = true;
+                dir::copy(&src, &dest, &options)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Version reported by `<manager> --version`
+    pub fn version(&self, folder: &Path) -> Result<String> {
+        let version = cmd!(self.to_string(), "--version")
+            .dir(folder)
+            .stderr_null()
+            .read()
+            .map_err(|err| format!("unable to run `{self}`: {err}"))?;
+        Ok(version.trim().to_string())
+    }
+
+    /// Version of the package manager resolved without running it (`--plan`):
+    /// the version declared by the `packageManager` field of `package.json`
+    /// (e.g. `yarn@3.6.1`) or, for yarn, `1.x` unless the project has a
+    /// `.yarnrc.yml` configuration (yarn 2+); `unknown` otherwise.
+    pub async fn declared_version(&self, folder: &Path) -> String {
+        let declared = fs::read_to_string(folder.join("package.json"))
+            .await
+            .ok()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+            .and_then(|json| json.get("packageManager")?.as_str().map(String::from));
+        let prefix = format!("{self}@");
+        if let Some(version) = declared.as_deref().and_then(|d| d.strip_prefix(&prefix)) {
+            // the version may be followed by a `+sha512.<hash>` suffix
+            return version.split('+').next().unwrap_or(version).to_string();
+        }
+
+        match self {
+            PackageManager::Yarn if folder.join(".yarnrc.yml").is_file().await => "2+".to_string(),
+            PackageManager::Yarn => "1.x".to_string(),
+            _ => "unknown".to_string(),
+        }
+    }
+
+    /// Command line installing packages. `version` is the version of
+    /// the package manager (yarn 2+ uses a different command set).
+    pub fn install_args(&self, version: &str, options: &InstallOptions) -> Result<Vec<String>> {
+        let mut argv = vec![self.to_string()];
+        match self {
+            PackageManager::Npm => {
+                argv.push(if options.frozen { "ci" } else { "install" }.into());
+                if !options.dev {
+                    argv.extend(["--omit".into(), "dev".into()]);
+                }
+                for workspace in options.workspaces.iter() {
+                    argv.extend(["--workspace".into(), workspace.clone()]);
+                }
+            }
+            PackageManager::Yarn if version.starts_with("1.") => {
+                if !options.workspaces.is_empty() {
+                    return Err(
+                        "workspace filtering is not supported by yarn 1.x, please use yarn 2+"
+                            .into(),
+                    );
+                }
+                argv.push("install".into());
+                if options.frozen {
+                    argv.push("--frozen-lockfile".into());
+                }
+                if !options.dev {
+                    argv.push("--production".into());
+                }
+            }
+            PackageManager::Yarn => {
+                if options.dev && options.workspaces.is_empty() {
+                    argv.push("install".into());
+                    if options.frozen {
+                        argv.push("--immutable".into());
+                    }
+                } else {
+                    // production and workspace-only installs are
+                    // performed using the `workspaces focus` command
+                    argv.extend(["workspaces".into(), "focus".into()]);
+                    if options.workspaces.is_empty() {
+                        argv.push("--all".into());
+                    } else {
+                        argv.extend(options.workspaces.iter().cloned());
+                    }
+                    if !options.dev {
+                        argv.push("--production".into());
+                    }
+                }
+            }
+            PackageManager::Pnpm => {
+                argv.push("install".into());
+                if options.frozen {
+                    argv.push("--frozen-lockfile".into());
+                }
+                if !options.dev {
+                    argv.push("--prod".into());
+                }
+                for workspace in options.workspaces.iter() {
+                    argv.extend(["--filter".into(), workspace.clone()]);
+                }
+            }
+        }
+
+        Ok(argv)
+    }
+
+    /// Environment variables (`NAME=value`) required by the install command.
+    /// `yarn workspaces focus` has no `--immutable` flag, so frozen yarn 2+
+    /// production and workspace installs enable immutable installs using
+    /// the equivalent configuration variable.
+    pub fn install_env(&self, version: &str, options: &InstallOptions) -> Vec<String> {
+        let focus = !options.dev || !options.workspaces.is_empty();
+        match self {
+            PackageManager::Yarn if !version.starts_with("1.") && focus && options.frozen => {
+                vec!["YARN_ENABLE_IMMUTABLE_INSTALLS=true".to_string()]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// Merge `node_modules` installed in the `staging` folder into the `target` folder
+pub async fn merge_node_modules(staging: &Path, target: &Path) -> Result<()> {
+    let src = staging.join("node_modules");
+    if !src.is_dir().await {
+        return Ok(());
+    }
+
+    let dest = target.join("node_modules");
+    fs::create_dir_all(&dest).await?;
+    let mut options = dir::CopyOptions::new();
+    options.content_only = true;
+    options.overwrite = true;
+    dir::copy(&src, &dest, &options)?;
+
+    Ok(())
+}
@@ -0,0 +1,365 @@
+use crate::prelude::*;
+use async_std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use console::style;
+
+/// Ordered build plan resolved from the manifest (`cargo nw build --plan`).
+/// Displays dependencies, build steps, actions, integrated files and
+/// redistributables without executing anything or modifying the filesystem.
+pub struct Plan {
+    builder: Builder,
+}
+
+impl Plan {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Plan {
+            builder: Builder::new(ctx),
+        }
+    }
+
+    pub fn with_common_tpl(mut self, common_tpl: Option<Tpl>) -> Self {
+        self.builder = self.builder.with_common_tpl(common_tpl);
+        self
+    }
+
+    #[allow(clippy::borrowed_box)]
+    pub async fn display(&self, targets: &TargetSet, installer: &Box<dyn Installer>) -> Result<()> {
+        let ctx = &self.builder.ctx;
+        let tpl = installer.tpl();
+        let target_folder = installer.target_folder();
+
+        log_info!(
+            "Plan",
+            "{} {} for {}-{}",
+            style(&ctx.manifest.application.title).cyan(),
+            ctx.manifest.application.version,
+            ctx.platform,
+            ctx.arch
+        );
+        if ctx.deps.release.is_unresolved() {
+            log_info!(
+                "NW",
+                "`{}` (unresolved, no cached release index)",
+                ctx.deps.release.spec
+            );
+        } else {
+            for meta in ctx.deps.get_targets() {
+                let folder = ctx.deps.dir.join(&meta.folder);
+                if folder.exists().await {
+                    log_info!("NW", "`{}` (cached)", meta.folder);
+                } else {
+                    log_info!("NW", "download `{}`", meta.url);
+                }
+            }
+        }
+        log_info!("Clean", "`{}`", ctx.build_folder.display());
+
+        for dep in ctx.manifest.dependencies.iter().flatten() {
+            self.dependency(dep, &tpl, &target_folder).await?;
+        }
+
+        for (index, build) in ctx.manifest.package.build.iter().flatten().enumerate() {
+            let label = format!("Step {}", index + 1);
+            if self.builder.is_common_step(build, &tpl)? {
+                log_info!(&label, "{} (skipped, not target-specific)", build.kind());
+                continue;
+            }
+            let step = match self.builder.resolve_step(index, build, &tpl).await? {
+                Some(step) => step,
+                None => {
+                    log_info!(&label, "{} (nothing to execute)", build.kind());
+                    continue;
+                }
+            };
+            if !step.force && step.fingerprint(ctx, &tpl)?.is_fresh().await? {
+                log_info!(&label, "{} (up to date)", build.kind());
+            } else {
+                log_info!(&label, "{}", build.kind());
+                for command in step.commands.iter() {
+                    log_command(&command.cwd, &command.args.get(&tpl));
+                }
+            }
+        }
+
+        self.actions(Stage::Build, &tpl, &[], targets, &target_folder)
+            .await?;
+
+        if ctx.deps.release.is_unresolved() {
+            log_info!("Integrate", "NW binaries (`{}`)", ctx.deps.release.spec);
+        } else {
+            log_info!(
+                "Integrate",
+                "NW binaries from `{}`",
+                ctx.deps.nwjs.target().display()
+            );
+        }
+        if let Some(ffmpeg) = &ctx.deps.ffmpeg {
+            log_info!(
+                "Integrate",
+                "FFMPEG binaries from `{}`",
+                ffmpeg.target().display()
+            );
+        }
+        let files = list_files_with_filters(
+            &ctx.app_root_folder,
+            &(&tpl, &ctx.include, &ctx.exclude).try_into()?,
+            &CopyOptions::new(ctx.manifest.package.hidden.unwrap_or(false)),
+        );
+        log_info!(
+            "Integrate",
+            "{} from `{}` to `{}`",
+            files_summary(&ctx.app_root_folder, &files),
+            ctx.app_root_folder.display(),
+            target_folder.display()
+        );
+        if let Some(overlay) = ctx.flavor.as_ref().and_then(|f| f.overlay.as_ref()) {
+            log_info!("Integrate", "flavor overlay `{overlay}`");
+        }
+
+        self.actions(Stage::Package, &tpl, &[], targets, &target_folder)
+            .await?;
+
+        let artifacts = installer.artifacts(targets);
+        for artifact in artifacts.iter() {
+            log_info!(
+                "Target",
+                "{} `{}`",
+                artifact.target,
+                artifact.file.display()
+            );
+        }
+
+        self.actions(Stage::Deploy, &tpl, &artifacts, targets, &target_folder)
+            .await?;
+        log_newline();
+
+        Ok(())
+    }
+
+    /// Fetch, build and copy operations of a dependency
+    async fn dependency(&self, dep: &Dependency, tpl: &Tpl, target_folder: &Path) -> Result<()> {
+        let ctx = &self.builder.ctx;
+        let name = self.builder.dependency_name(dep, tpl);
+
+        if let Some(platform) = &dep.platform {
+            if !platform.contains(&ctx.platform) {
+                log_info!("Dependency", "`{name}` (skipped on `{}`)", ctx.platform);
+                return Ok(());
+            }
+        }
+        if let Some(arch) = &dep.arch {
+            if !arch.contains(&ctx.arch) {
+                log_info!("Dependency", "`{name}` (skipped on `{}`)", ctx.arch);
+                return Ok(());
+            }
+        }
+
+        // `rev` is `None` if the revision changes (the dependency is rebuilt)
+        let (folder, rev) = if let Some(git) = &dep.git {
+            let folder = ctx.dependencies_folder.join(git.repository());
+            let rev = if !folder.is_dir().await {
+                let reference = git.branch.as_ref().or(git.tag.as_ref());
+                log_info!(
+                    "Dependency",
+                    "`{name}`: clone `{}`{}",
+                    git.url,
+                    reference.map(|r| format!(" ({r})")).unwrap_or_default()
+                );
+                None
+            } else {
+                let head = git_rev(&folder, "HEAD");
+                let pinned = match (&git.rev, &git.tag) {
+                    (Some(rev), _) => Some(rev.clone()),
+                    (None, Some(tag)) => Some(format!("refs/tags/{tag}")),
+                    (None, None) if ctx.update => None,
+                    (None, None) => ctx.lock.dependency_rev(git),
+                };
+                match pinned {
+                    Some(pinned) if git_rev(&folder, &format!("{pinned}^{{commit}}")) == head => {
+                        log_info!("Dependency", "`{name}`: at {pinned}");
+                        head
+                    }
+                    Some(pinned) => {
+                        log_info!("Dependency", "`{name}`: checkout {pinned}");
+                        None
+                    }
+                    None => {
+                        let branch = git.branch.as_deref().unwrap_or("default branch");
+                        log_info!("Dependency", "`{name}`: pull `{branch}`");
+                        head
+                    }
+                }
+            };
+            (folder, rev)
+        } else if let Some(path) = &dep.path {
+            let folder = ctx.project_root_folder.join(tpl.transform(path));
+            log_info!("Dependency", "`{name}`: local `{}`", folder.display());
+            let rev = path_dependency_rev(&folder);
+            (folder, rev)
+        } else if let Some(url) = &dep.url {
+            let url = prebuilt_url(url, tpl);
+            let archive = self.builder.prebuilt_archive(&url);
+            let rev = if archive.is_file().await {
+                log_info!("Dependency", "`{name}`: cached `{}`", archive.display());
+                Some(sha256sum(&archive)?)
+            } else {
+                log_info!("Dependency", "`{name}`: download `{url}`");
+                None
+            };
+            (ctx.dependencies_folder.join(&name), rev)
+        } else {
+            log_info!("Dependency", "`{name}`");
+            (ctx.dependencies_folder.clone(), None)
+        };
+
+        let up_to_date = match rev {
+            Some(rev) => {
+                let (status_file, status_data) =
+                    self.builder.dependency_status(dep, &name, &rev, tpl)?;
+                fs::read_to_string(status_file).await.ok() == Some(status_data)
+            }
+            None => false,
+        };
+        if up_to_date {
+            log_info!("Dependency", "`{name}`: build is up to date");
+        } else {
+            for ec in dep.run.iter() {
+                if is_active(ctx, &ec.family, &ec.platform, &ec.arch) {
+                    let cwd = resolve_cwd(ctx, ec, Some(&folder), tpl)?;
+                    log_command(&cwd, &ec.get_args()?.get(tpl));
+                }
+            }
+        }
+
+        for copy_settings in dep.copy.iter() {
+            log_copy(tpl, copy_settings, &folder, target_folder).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Actions executed at `stage` with their resolved templates
+    async fn actions(
+        &self,
+        stage: Stage,
+        tpl: &Tpl,
+        artifacts: &[Artifact],
+        targets: &TargetSet,
+        target_folder: &Path,
+    ) -> Result<()> {
+        let ctx = &self.builder.ctx;
+        let tpl = tpl.extend(&artifacts_tpl(artifacts));
+        let src_folder = &ctx.project_root_folder;
+        for action in ctx.manifest.action.iter().flatten() {
+            if !action.is_active(&stage, ctx, &tpl, targets)? {
+                continue;
+            }
+
+            log_info!(
+                "Action",
+                "{} ({stage:?} stage)",
+                action.name.as_deref().unwrap_or("...")
+            );
+            let tpls = match action.foreach {
+                Some(Foreach::Artifacts) => artifacts
+                    .iter()
+                    .map(|artifact| tpl.extend(&artifact.tpl()))
+                    .collect(),
+                None => vec![tpl.clone()],
+            };
+            for tpl in tpls.iter() {
+                for item in action.items.iter() {
+                    if !item.is_active(&stage, ctx, tpl, targets)? {
+                        continue;
+                    }
+                    if let Some(ec) = &item.run {
+                        if is_active(ctx, &ec.family, &ec.platform, &ec.arch) {
+                            let cwd = resolve_cwd(ctx, ec, Some(src_folder), tpl)?;
+                            log_command(&cwd, &ec.get_args()?.get(tpl));
+                        }
+                    }
+                    if let Some(copy_settings) = &item.copy {
+                        log_copy(tpl, copy_settings, src_folder, target_folder).await?;
+                    }
+                    if let Some(write) = &item.write {
+                        log_info!("Write", "`{}`", tpl.transform(&write.file));
+                    }
+                    if let Some(script) = &item.script {
+                        let name = script
+                            .name
+                            .clone()
+                            .unwrap_or_else(|| script.kind.to_string());
+                        log_info!("Script", "{name} in `{}`", src_folder.display());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve `reference` in a git repository
+fn git_rev(folder: &Path, reference: &str) -> Option<String> {
+    cmd("git", ["rev-parse", "--verify", "--quiet", reference])
+        .dir(folder)
+        .stderr_null()
+        .read()
+        .ok()
+        .map(|rev| rev.trim().to_string())
+}
+
+fn log_command(cwd: &Path, argv: &[String]) {
+    let argv = argv
+        .iter()
+        .map(|arg| {
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                format!("{arg:?}")
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+    log_info!("Run", "`{}` in `{}`", argv.join(" "), cwd.display());
+}
+
+/// Files matched by a copy directive (see [`copy`])
+async fn log_copy(tpl: &Tpl, copy: &Copy, src_folder: &Path, target_folder: &Path) -> Result<()> {
+    if let Some(file) = &copy.file {
+        let from = normalize(src_folder.join(tpl.transform(file)))?;
+        log_info!(
+            "Copy",
+            "`{}` to `{}`",
+            from.display(),
+            tpl.transform(&copy.to)
+        );
+    } else {
+        let to_folder = normalize(target_folder.join(tpl.transform(&copy.to)))?;
+        let options = CopyOptions {
+            hidden: copy.hidden.unwrap_or(false),
+            flatten: true,
+        };
+        let files = if src_folder.is_dir().await {
+            let files = list_files_with_filters(src_folder, &(tpl, copy).try_into()?, &options);
+            files_summary(src_folder, &files)
+        } else {
+            // the folder is created by a preceding step
+            format!("files from `{}`", src_folder.display())
+        };
+        log_info!("Copy", "{files} to `{}`", to_folder.display());
+    }
+
+    Ok(())
+}
+
+/// File count and total size of `files` (relative to `folder`)
+fn files_summary(folder: &Path, files: &[PathBuf]) -> String {
+    let size = files
+        .iter()
+        .filter_map(|file| std::fs::metadata(folder.join(file)).ok())
+        .map(|metadata| metadata.len())
+        .sum::<u64>();
+    format!("{} files ({})", files.len(), format_size(size))
+}
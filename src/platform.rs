@@ -184,6 +184,28 @@ impl FromStr for Architecture {
 //     format!("{}-{}", platform, arch)
 // }
 
+/// Rust target triple for the given platform and architecture
+pub fn get_rust_target_triple(platform: &Platform, arch: &Architecture) -> Result<String> {
+    let triple = match (platform, arch) {
+        (Platform::Windows, Architecture::x64) => "x86_64-pc-windows-msvc",
+        (Platform::Windows, Architecture::ia32) => "i686-pc-windows-msvc",
+        (Platform::Windows, Architecture::arm64 | Architecture::aarch64) => {
+            "aarch64-pc-windows-msvc"
+        }
+        (Platform::Linux, Architecture::x64) => "x86_64-unknown-linux-gnu",
+        (Platform::Linux, Architecture::ia32) => "i686-unknown-linux-gnu",
+        (Platform::Linux, Architecture::arm64 | Architecture::aarch64) => {
+            "aarch64-unknown-linux-gnu"
+        }
+        (Platform::MacOS, Architecture::x64) => "x86_64-apple-darwin",
+        (Platform::MacOS, Architecture::arm64 | Architecture::aarch64) => "aarch64-apple-darwin",
+        (Platform::MacOS, Architecture::ia32) => {
+            return Err("ia32 architecture is not supported on MacOS".into());
+        }
+    };
+    Ok(triple.to_string())
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum PlatformFamily {
@@ -204,6 +226,16 @@ impl Default for PlatformFamily {
     }
 }
 
+/// Family of the target platform (the default is the family of the host)
+impl From<Platform> for PlatformFamily {
+    fn from(platform: Platform) -> Self {
+        match platform {
+            Platform::Windows => PlatformFamily::Windows,
+            Platform::Linux | Platform::MacOS => PlatformFamily::Unix,
+        }
+    }
+}
+
 impl FromStr for PlatformFamily {
     type Err = Error;
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -1,7 +1,9 @@
 pub use crate::{
-    action::*, archive::*, builder::*, context::*, copy::*, deps::*, error::*, exec::*, images::*,
-    init::TemplateKind, installer::*, log::*, manifest::*, platform::*, result::*, runner::*,
-    script::*, signatures::*, tpl::*, utils::*, utils::*,
+    action::*, addons::*, archive::*, builder::*, cache::*, cargo::*, context::*, copy::*, deps::*,
+    download::*, error::*, exec::*, expr::*, filelock::*, fingerprint::*, images::*, info::*,
+    init::TemplateKind, installer::*, lock::*, log::*, manifest::*, npm::*, plan::*, platform::*,
+    report::*, runner::*, script::*, signatures::*, tpl::*, utils::*, versions::*, wasm::*,
+    watch::*,
 };
 
 pub use crate::result::Result;
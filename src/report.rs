@@ -0,0 +1,235 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use std::{fmt, str::FromStr};
+
+/// Name of the build report written into the output folder
+pub const BUILD_REPORT_FILE: &str = "build-report.json";
+
+/// Format of build messages (`--message-format`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Human-readable log output
+    #[default]
+    Human,
+    /// JSON events on stdout, log output on stderr
+    Json,
+}
+
+impl fmt::Display for MessageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageFormat::Human => write!(f, "human"),
+            MessageFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for MessageFormat {
+    type Err = Error;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(format!("unsupported message format: {s} (must be 'human' or 'json')").into()),
+        }
+    }
+}
+
+/// Status of a dependency or a build step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    /// Executed during this build
+    Executed,
+    /// Up to date, execution skipped
+    Fresh,
+    /// Not applicable to the package being built
+    Skipped,
+}
+
+/// Build event emitted as a JSON line with `--message-format json`.
+/// `package` identifies the package being built (the `app_snake_name`).
+#[derive(Debug, Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    BuildStarted {
+        package: &'a str,
+        platform: &'a Platform,
+        arch: &'a Architecture,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        flavor: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        variant: Option<&'a str>,
+    },
+    Dependency {
+        package: &'a str,
+        name: &'a str,
+        status: StepStatus,
+    },
+    BuildStep {
+        package: &'a str,
+        index: usize,
+        kind: &'a str,
+        status: StepStatus,
+        duration: f64,
+    },
+    Action {
+        package: &'a str,
+        stage: &'a Stage,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<&'a str>,
+    },
+    Target {
+        package: &'a str,
+        target: &'a Target,
+    },
+    Artifact {
+        package: &'a str,
+        #[serde(flatten)]
+        artifact: &'a ArtifactReport,
+    },
+    BuildFinished {
+        success: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        report: Option<&'a std::path::Path>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+/// Print the event on stdout if JSON messages are enabled
+pub fn emit(event: &Event) {
+    if json_messages() {
+        match serde_json::to_string(event) {
+            Ok(json) => println!("{json}"),
+            Err(err) => log_warn!("Report", "unable to serialize build event: {err}"),
+        }
+    }
+}
+
+/// Redistributable produced by the build
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ArtifactReport {
+    pub path: std::path::PathBuf,
+    pub target: Target,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl ArtifactReport {
+    pub fn try_new(artifact: &Artifact) -> Result<ArtifactReport> {
+        Ok(ArtifactReport {
+            path: artifact.file.clone().into(),
+            target: artifact.target.clone(),
+            size: std::fs::metadata(&artifact.file)?.len(),
+            sha256: sha256sum(&artifact.file)?,
+        })
+    }
+}
+
+/// Durations of build phases in seconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Durations {
+    pub dependencies: f64,
+    pub build_steps: f64,
+    pub package: f64,
+    pub total: f64,
+}
+
+/// Package produced for a single platform, architecture, flavor and variant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackageReport {
+    pub name: String,
+    pub title: String,
+    pub version: String,
+    pub platform: Platform,
+    pub arch: Architecture,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flavor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    pub nw_version: String,
+    pub sdk: bool,
+    pub output_folder: std::path::PathBuf,
+    pub durations: Durations,
+    pub artifacts: Vec<ArtifactReport>,
+}
+
+impl PackageReport {
+    pub fn new(ctx: &Context) -> PackageReport {
+        PackageReport {
+            name: ctx.app_snake_name.clone(),
+            title: ctx.manifest.application.title.clone(),
+            version: ctx.manifest.application.version.clone(),
+            platform: ctx.platform.clone(),
+            arch: ctx.arch.clone(),
+            flavor: ctx.flavor.as_ref().map(|flavor| flavor.name.clone()),
+            variant: ctx.variant.as_ref().map(|variant| variant.name.clone()),
+            nw_version: ctx.deps.release.version.clone(),
+            sdk: ctx.sdk,
+            output_folder: ctx.output_folder.clone().into(),
+            durations: Durations::default(),
+            artifacts: Vec::new(),
+        }
+    }
+}
+
+/// Contents of `build-report.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildReport {
+    /// Git revision of the project (if the project is a git repository)
+    pub git_revision: Option<String>,
+    /// Build start time (RFC 3339)
+    pub timestamp: String,
+    /// Total build duration in seconds
+    pub duration: f64,
+    pub packages: Vec<PackageReport>,
+}
+
+impl BuildReport {
+    pub fn new(project_folder: &Path, timestamp: String, duration: f64) -> BuildReport {
+        let git_revision = cmd!("git", "rev-parse", "HEAD")
+            .dir(project_folder)
+            .stderr_null()
+            .read()
+            .ok()
+            .map(|rev| rev.trim().to_string());
+
+        BuildReport {
+            git_revision,
+            timestamp,
+            duration,
+            packages: Vec::new(),
+        }
+    }
+
+    /// Load the report written by a previous build into `output_folder`
+    pub async fn load(output_folder: &Path) -> Result<BuildReport> {
+        let file = output_folder.join(BUILD_REPORT_FILE);
+        let text = fs::read_to_string(&file)
+            .await
+            .map_err(|err| format!("unable to read `{}`: {err}", file.display()))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Artifacts of all packages in the report
+    pub fn artifacts(&self) -> Vec<Artifact> {
+        self.packages
+            .iter()
+            .flat_map(|package| package.artifacts.iter())
+            .map(|artifact| Artifact::new(artifact.target.clone(), artifact.path.clone().into()))
+            .collect()
+    }
+
+    /// Write the report into `output_folder`
+    pub async fn store(&self, output_folder: &Path) -> Result<PathBuf> {
+        let file = output_folder.join(BUILD_REPORT_FILE);
+        fs::write(&file, serde_json::to_string_pretty(self)?).await?;
+        Ok(file)
+    }
+}
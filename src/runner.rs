@@ -1,36 +1,252 @@
 use crate::prelude::*;
-use std::path::PathBuf;
+use async_std::{
+    fs,
+    path::{Path, PathBuf},
+    task::sleep,
+};
+use fs_extra::dir;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// Interval between checks for modified files
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Bursts of changes are collected until files
+/// remain unmodified for this duration
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Rebuild and reload the application when source files change
+    pub watch: bool,
+    /// Run the integrated application from the build folder
+    pub packaged: bool,
+    /// Chromium profile folder (overrides the manifest setting)
+    pub user_data_dir: Option<String>,
+    /// Enable remote debugging (DevTools protocol) on this port
+    pub remote_debugging_port: Option<u16>,
+    /// Arguments passed to the application
+    pub args: Vec<String>,
+}
 
 pub struct Runner {
     ctx: Arc<Context>,
+    options: RunOptions,
 }
 
 impl Runner {
     pub fn new(ctx: Arc<Context>) -> Self {
-        Self { ctx }
+        Self {
+            ctx,
+            options: RunOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
     }
 
     pub async fn run(&self) -> Result<()> {
-        self.ctx.deps.ensure().await?;
+        if self.options.watch && self.options.packaged {
+            return Err("`--watch` can not be used with `--packaged`".into());
+        }
 
-        let nwjs_folder = &self.ctx.deps.nwjs.target();
+        self.ctx.deps.ensure(&self.ctx.lock, false).await?;
+        self.ctx.lock.store().await?;
+        let (nw, folder) = if self.options.packaged {
+            self.packaged_executable().await?
+        } else {
+            (
+                self.nwjs_executable().await?,
+                self.ctx.app_root_folder.clone(),
+            )
+        };
+
+        if self.options.watch {
+            return self.watch(&nw, &folder).await;
+        }
+
+        // cmd!("ls -la").dir(folder.clone()).run()?;
+        // cmd!("pwd").dir(folder.clone()).run()?;
+        self.command(&nw, &folder).await?.run()?;
+
+        Ok(())
+    }
+
+    /// NW executable from the cached distribution. If FFMPEG is enabled,
+    /// the distribution is copied to the cache folder and the FFMPEG
+    /// library is integrated into the copy.
+    async fn nwjs_executable(&self) -> Result<PathBuf> {
+        let mut nwjs_folder = self.ctx.deps.nwjs.target();
+
+        if let Some(ffmpeg) = &self.ctx.deps.ffmpeg {
+            let run_folder = self
+                .ctx
+                .cache_folder
+                .join("run")
+                .join(&self.ctx.deps.nwjs.folder);
+            if !run_folder.exists().await {
+                log_info!("Run", "preparing NW distribution with FFMPEG");
+            }
+            fs::create_dir_all(&run_folder).await?;
+            let mut options = dir::CopyOptions::new();
+            options.content_only = true;
+            options.skip_exist = true;
+            dir::copy(&nwjs_folder, &run_folder, &options)?;
+
+            cfg_if! {
+                if #[cfg(target_os = "windows")] {
+                    let (library, destination) = ("ffmpeg.dll", run_folder.clone());
+                } else if #[cfg(target_os = "macos")] {
+                    let frameworks = run_folder
+                        .join("nwjs.app/Contents/Frameworks/nwjs Framework.framework/Versions");
+                    let version = fs::read_to_string(frameworks.join("Current")).await?;
+                    let (library, destination) = ("libffmpeg.dylib", frameworks.join(version.trim()));
+                } else {
+                    let (library, destination) = ("libffmpeg.so", run_folder.join("lib"));
+                }
+            }
+            fs::create_dir_all(&destination).await?;
+            fs::copy(ffmpeg.target().join(library), destination.join(library)).await?;
+            nwjs_folder = run_folder;
+        }
 
         cfg_if! {
             if #[cfg(target_os = "windows")] {
-                let nw = PathBuf::from(nwjs_folder).join("nw.exe");
+                let nw = nwjs_folder.join("nw.exe");
             } else if #[cfg(target_os = "macos")] {
-                let nwjs_folder = nwjs_folder.join("nwjs.app/Contents/MacOS");
-                let nw = PathBuf::from(&nwjs_folder).join("nwjs");
+                let nw = nwjs_folder.join("nwjs.app/Contents/MacOS/nwjs");
             } else {
-                let nw = PathBuf::from(nwjs_folder).join("nw");
+                let nw = nwjs_folder.join("nw");
             }
         }
 
-        println!("{}", nwjs_folder.display());
-        // cmd!("ls -la").dir(folder.clone()).run()?;
-        // cmd!("pwd").dir(folder.clone()).run()?;
-        let folder = self.ctx.app_root_folder.clone();
-        cmd!(nw, ".").dir(folder).run()?;
+        Ok(nw)
+    }
+
+    /// Executable and folder of the application integrated by `cargo nw build`
+    async fn packaged_executable(&self) -> Result<(PathBuf, PathBuf)> {
+        let installer = create_installer(&self.ctx)?;
+        let nw = installer.executable();
+        if !nw.is_file().await {
+            return Err(format!(
+                "`{}` not found, please run `cargo nw build` first",
+                nw.display()
+            )
+            .into());
+        }
+        let folder = nw.parent().unwrap().to_path_buf();
+        Ok((nw, folder))
+    }
+
+    async fn command(&self, nw: &Path, folder: &Path) -> Result<duct::Expression> {
+        let tpl = self.ctx.tpl();
+        let settings = self.ctx.manifest.run.clone().unwrap_or_default();
+
+        let user_data_dir = match self
+            .options
+            .user_data_dir
+            .as_ref()
+            .or(settings.user_data_dir.as_ref())
+        {
+            Some(folder) => self.ctx.project_root_folder.join(tpl.transform(folder)),
+            None => self.ctx.temp_folder.join("profile"),
+        };
+        fs::create_dir_all(&user_data_dir).await?;
+
+        let mut argv = vec![format!("--user-data-dir={}", user_data_dir.display())];
+        if let Some(port) = self.options.remote_debugging_port {
+            argv.push(format!("--remote-debugging-port={port}"));
+        }
+        if !self.options.packaged {
+            argv.push(".".to_string());
+        }
+        argv.extend(settings.args.iter().flatten().map(|arg| tpl.transform(arg)));
+        argv.extend(self.options.args.iter().cloned());
+
+        log_info!("Run", "{}", nw.display());
+        let mut expression = duct::cmd(nw.as_os_str(), argv).dir(folder);
+        if let Some(env) = &settings.env {
+            let env = env.iter().map(|def| tpl.transform(def)).collect();
+            for (k, v) in get_env_defs(&env)? {
+                expression = expression.env(k, v);
+            }
+        }
+        Ok(expression)
+    }
+
+    /// Execute the build steps, run the application and, when the inputs
+    /// of a build step change, re-execute the step and restart the
+    /// application. Changes to the application folder restart the
+    /// application only. Build steps integrate their output into the
+    /// application folder.
+    async fn watch(&self, nw: &Path, folder: &Path) -> Result<()> {
+        let builder = Builder::new(self.ctx.clone());
+        let folder = folder.to_path_buf();
+        let tpl = create_installer_tpl(&self.ctx, &folder);
+        let builds = self.ctx.manifest.package.build.clone().unwrap_or_default();
+
+        for (index, build) in builds.iter().enumerate() {
+            builder.build_step(index, build, &tpl, &folder).await?;
+        }
+
+        let mut steps = Vec::new();
+        for (index, build) in builds.iter().enumerate() {
+            if let Some(step) = builder.resolve_step(index, build, &tpl).await? {
+                if !step.inputs.is_empty() {
+                    steps.push((index, WatchSet::try_new(&step.folder, &step.inputs)?));
+                }
+            }
+        }
+        let mut app = WatchSet::try_new(&folder, &["**".to_string()])?;
+
+        log_info!("Watch", "watching `{}` for changes", folder.display());
+        let mut process = self.command(nw, &folder).await?.unchecked().start()?;
+        loop {
+            sleep(POLL_INTERVAL).await;
+            if process.try_wait()?.is_some() {
+                break;
+            }
+
+            let mut changed = BTreeSet::new();
+            let mut reload = false;
+            loop {
+                let mut modified = app.poll()?;
+                for (index, set) in steps.iter_mut() {
+                    if set.poll()? {
+                        changed.insert(*index);
+                        modified = true;
+                    }
+                }
+                if !modified {
+                    break;
+                }
+                reload = true;
+                sleep(DEBOUNCE).await;
+            }
+            if !reload {
+                continue;
+            }
+
+            for index in changed.iter() {
+                if let Err(err) = builder
+                    .build_step(*index, &builds[*index], &tpl, &folder)
+                    .await
+                {
+                    log_warn!("Watch", "build step {} failed: {err}", index + 1);
+                }
+            }
+
+            // ignore changes made by the build steps
+            app.reset()?;
+            for (_, set) in steps.iter_mut() {
+                set.reset()?;
+            }
+
+            log_info!("Watch", "reloading");
+            process.kill()?;
+            process = self.command(nw, &folder).await?.unchecked().start()?;
+        }
 
         Ok(())
     }
@@ -15,9 +15,9 @@ pub enum ScriptKind {
     Other(String),
 }
 
-impl ToString for ScriptKind {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for ScriptKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
             ScriptKind::Bash => "bash",
             ScriptKind::Sh => "sh",
             ScriptKind::Zsh => "zsh",
@@ -25,8 +25,7 @@ impl ToString for ScriptKind {
             ScriptKind::Cmd => "cmd",
             ScriptKind::Ps1 => "ps1",
             ScriptKind::Other(s) => s.as_str(),
-        }
-        .to_string()
+        })
     }
 }
 
@@ -75,14 +74,14 @@ impl Script {
             .name
             .clone()
             .map(|s| format!("'{s}'"))
-            .unwrap_or_else(|| "".to_string());
+            .unwrap_or_default();
         log_info!("Script", "running script {name}");
         let file = format!(
             "{}.{}",
             self.name
                 .clone()
                 .unwrap_or_else(|| Uuid::new_v4().to_string()),
-            self.kind.to_string()
+            self.kind
         );
         let mut argv = self
             .interpreter
@@ -98,7 +97,11 @@ impl Script {
         argv.push(file.to_str().unwrap().to_string());
         let proc = argv.remove(0);
 
-        cmd(proc, argv).dir(cwd).full_env(&tpl.map).run()?;
+        cmd(proc, argv)
+            .dir(cwd)
+            .full_env(&tpl.map)
+            .stdout_to_log()
+            .run()?;
 
         Ok(())
     }
@@ -26,3 +26,12 @@ pub async fn generate_signatures(file: &Path, signatures: &[Signature]) -> Resul
     }
     Ok(())
 }
+
+/// Returns hex-encoded SHA256 hash of the file contents
+pub fn sha256sum(file: &Path) -> Result<String> {
+    let mut reader = std::fs::File::open(file)
+        .map_err(|err| format!("Unable to read {}: {err}", file.to_string_lossy()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
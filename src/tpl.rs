@@ -40,7 +40,7 @@ impl Tpl {
     pub fn new() -> Tpl {
         let mut map = HashMap::new();
         for (k, v) in std::env::vars() {
-            map.insert(format!("${}", k.to_uppercase()), v.to_string());
+            map.insert(k.to_uppercase(), v.to_string());
         }
 
         Tpl { map }
@@ -58,14 +58,17 @@ impl Tpl {
                 .map
                 .clone()
                 .into_iter()
-                .chain(tpl.map.clone().into_iter())
+                .chain(tpl.map.clone())
                 .collect(),
         }
     }
 
     pub fn transform(&self, text: &str) -> String {
         let mut text = text.to_string();
-        for (k, v) in self.map.iter() {
+        // longer keys first so that e.g. `$ARCH` does not clobber `$ARCHIVE_FILE`
+        let mut entries = self.map.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(k, _)| std::cmp::Reverse(k.len()));
+        for (k, v) in entries {
             text = text.replace(&format!("${k}"), v);
             // text = text.replace(&format!("__{k}__"), v);
             text = text.replace(&format!("___{k}___"), v);
@@ -48,10 +48,9 @@ pub fn get_env_defs(strings: &Vec<String>) -> Result<Vec<(String, String)>> {
     let mut parsed_strings = Vec::new();
 
     for string in strings {
-        let captures = regex.captures(string).unwrap();
-        if captures.len() != 2 {
+        let Some(captures) = regex.captures(string) else {
             return Err(format!("Error parsing the environment string: '{string}'").into());
-        }
+        };
         let a = captures[1].to_string();
         let b = captures[2].to_string();
 
@@ -65,13 +64,7 @@ pub fn normalize<P>(path: P) -> Result<PathBuf>
 where
     P: AsRef<Path>,
 {
-    cfg_if! {
-        if #[cfg(platform = "windows")] {
-            normalize_with_separator(path.as_ref(), "\\")
-        } else {
-            normalize_with_separator(path.as_ref(), "/")
-        }
-    }
+    normalize_with_separator(path.as_ref(), "/")
 }
 
 pub fn normalize_with_separator(path: &Path, separator: &str) -> Result<PathBuf> {
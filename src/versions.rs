@@ -0,0 +1,286 @@
+use crate::prelude::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use semver::{Version, VersionReq};
+
+/// Default location of the NW release index
+pub const NWJS_VERSIONS_URL: &str = "https://nwjs.io/versions.json";
+
+/// NW release index (`versions.json`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionIndex {
+    pub latest: String,
+    pub stable: String,
+    pub lts: String,
+    pub versions: Vec<VersionInfo>,
+}
+
+/// NW release entry in the `versions.json` index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    /// Release version in the `v0.70.1` format
+    pub version: String,
+    pub date: Option<String>,
+    /// Available distributions in the `<platform>-<arch>` format
+    pub files: Option<Vec<String>>,
+    pub flavors: Option<Vec<String>>,
+    pub components: Option<Components>,
+}
+
+/// Chromium and Node versions bundled with the NW release
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Components {
+    pub node: Option<String>,
+    pub chromium: Option<String>,
+}
+
+impl VersionInfo {
+    pub fn version(&self) -> &str {
+        self.version.trim_start_matches('v')
+    }
+
+    pub fn semver(&self) -> Option<Version> {
+        Version::parse(self.version()).ok()
+    }
+
+    pub fn node(&self) -> Option<&str> {
+        self.components.as_ref().and_then(|c| c.node.as_deref())
+    }
+
+    pub fn chromium(&self) -> Option<&str> {
+        self.components.as_ref().and_then(|c| c.chromium.as_deref())
+    }
+
+    pub fn has_file(&self, file: &str) -> bool {
+        self.files
+            .as_ref()
+            .map(|files| files.iter().any(|f| f == file))
+            .unwrap_or(true)
+    }
+}
+
+impl VersionIndex {
+    /// Location of the local copy of the last fetched index
+    pub fn cache_file() -> PathBuf {
+        get_deps_folder().join("versions.json")
+    }
+
+    /// Load the index from a local file or an `http(s)://` url.
+    /// Remote indexes are stored in the cache folder and the
+    /// cached copy is used if the index can not be fetched.
+    pub async fn load(location: &str, folder: &Path) -> Result<VersionIndex> {
+        if !is_url(location) {
+            return Self::load_file(&folder.join(location)).await;
+        }
+
+        log_info!("NW", "fetching release index `{}`", location);
+        match Self::fetch(location).await {
+            Ok(text) => {
+                let index: VersionIndex = serde_json::from_str(&text)?;
+                let cache_file = Self::cache_file();
+                if let Some(parent) = cache_file.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+                fs::write(&cache_file, text).await?;
+                Ok(index)
+            }
+            Err(err) => {
+                let cache_file = Self::cache_file();
+                if cache_file.exists().await {
+                    log_warn!("NW", "unable to fetch `{location}`: {err}");
+                    log_warn!("NW", "using cached index `{}`", cache_file.display());
+                    Self::load_file(&cache_file).await
+                } else {
+                    Err(format!("unable to fetch NW release index `{location}`: {err}").into())
+                }
+            }
+        }
+    }
+
+    /// Load the index without network access (a local index file
+    /// or a cached copy of the remote index), if available.
+    pub async fn load_offline(location: &str, folder: &Path) -> Result<Option<VersionIndex>> {
+        let file = if is_url(location) {
+            Self::cache_file()
+        } else {
+            folder.join(location)
+        };
+
+        if file.exists().await {
+            Ok(Some(Self::load_file(&file).await?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn load_file(file: &Path) -> Result<VersionIndex> {
+        let text = fs::read_to_string(file)
+            .await
+            .map_err(|err| format!("unable to read `{}`: {err}", file.display()))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    async fn fetch(url: &str) -> Result<String> {
+        let response = reqwest::get(url).await?.error_for_status()?;
+        Ok(response.text().await?)
+    }
+
+    pub fn find(&self, version: &str) -> Option<&VersionInfo> {
+        let version = version.trim_start_matches('v');
+        self.versions.iter().find(|info| info.version() == version)
+    }
+
+    /// Resolve a version specification (`latest`, `stable`, `lts`,
+    /// a partial version such as `0.80` or a semver requirement such
+    /// as `^0.78`) against the index. `file` is the `<platform>-<arch>`
+    /// distribution that must be available for the resolved release.
+    pub fn resolve(&self, spec: &str, file: &str) -> Result<&VersionInfo> {
+        let keyword = match spec {
+            "latest" => Some(&self.latest),
+            "stable" => Some(&self.stable),
+            "lts" => Some(&self.lts),
+            _ => None,
+        };
+
+        if let Some(version) = keyword {
+            return self.find(version).ok_or_else(|| {
+                format!("NW release index does not contain `{spec}` version `{version}`").into()
+            });
+        }
+
+        let req = VersionReq::parse(spec.trim_start_matches('v'))
+            .map_err(|err| format!("invalid NW version `{spec}`: {err}"))?;
+
+        let mut candidates = self
+            .versions
+            .iter()
+            .filter_map(|info| info.semver().map(|version| (version, info)))
+            .filter(|(version, info)| req.matches(version) && info.has_file(file))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        candidates.first().map(|(_, info)| *info).ok_or_else(|| {
+            format!("unable to find NW release matching `{spec}` for `{file}`").into()
+        })
+    }
+}
+
+/// NW version resolved from the `nwjs.version` manifest setting
+#[derive(Debug, Clone)]
+pub struct NwRelease {
+    /// Version specification as declared in the manifest or on the command line
+    pub spec: String,
+    /// Exact NW version (without the `v` prefix)
+    pub version: String,
+    /// Chromium version bundled with this release (if known)
+    pub chromium: Option<String>,
+    /// Node version bundled with this release (if known)
+    pub node: Option<String>,
+}
+
+impl NwRelease {
+    /// Resolve the version specification. Exact versions are used as-is
+    /// and do not require network access; the release index is consulted
+    /// for all other specifications.
+    pub async fn resolve(
+        spec: &str,
+        nwjs: &NWJS,
+        folder: &Path,
+        platform: &Platform,
+        arch: &Architecture,
+    ) -> Result<NwRelease> {
+        let location = nwjs.index.as_deref().unwrap_or(NWJS_VERSIONS_URL);
+        let spec = spec.trim();
+
+        if is_exact_version(spec) {
+            let version = spec.trim_start_matches('v').to_string();
+            let index = VersionIndex::load_offline(location, folder).await?;
+            let info = index.as_ref().and_then(|index| index.find(&version));
+            return Ok(NwRelease {
+                spec: spec.to_string(),
+                chromium: info.and_then(|info| info.chromium().map(String::from)),
+                node: info.and_then(|info| info.node().map(String::from)),
+                version,
+            });
+        }
+
+        let file = format!("{}-{}", get_nwjs_suffix(platform), arch.to_nwjs_arch());
+        let index = VersionIndex::load(location, folder).await?;
+        let info = index.resolve(spec, &file)?;
+
+        let release = NwRelease {
+            spec: spec.to_string(),
+            version: info.version().to_string(),
+            chromium: info.chromium().map(String::from),
+            node: info.node().map(String::from),
+        };
+
+        log_info!(
+            "NW",
+            "resolved `{}` to version `{}`",
+            release.spec,
+            release.version
+        );
+
+        Ok(release)
+    }
+
+    /// Resolve the version specification without network access, using
+    /// a local or cached release index (see [`VersionIndex::load_offline`]).
+    /// If there is no index, the release is left unresolved (the version
+    /// is the specification, see [`NwRelease::is_unresolved`]).
+    pub async fn resolve_offline(
+        spec: &str,
+        nwjs: &NWJS,
+        folder: &Path,
+        platform: &Platform,
+        arch: &Architecture,
+    ) -> Result<NwRelease> {
+        let location = nwjs.index.as_deref().unwrap_or(NWJS_VERSIONS_URL);
+        let spec = spec.trim();
+        if is_exact_version(spec) {
+            return Self::resolve(spec, nwjs, folder, platform, arch).await;
+        }
+
+        let release = match VersionIndex::load_offline(location, folder).await? {
+            Some(index) => {
+                let file = format!("{}-{}", get_nwjs_suffix(platform), arch.to_nwjs_arch());
+                let info = index.resolve(spec, &file)?;
+                NwRelease {
+                    spec: spec.to_string(),
+                    version: info.version().to_string(),
+                    chromium: info.chromium().map(String::from),
+                    node: info.node().map(String::from),
+                }
+            }
+            None => NwRelease {
+                spec: spec.to_string(),
+                version: spec.to_string(),
+                chromium: None,
+                node: None,
+            },
+        };
+
+        Ok(release)
+    }
+
+    /// `true` if the version specification could not be resolved
+    /// to an exact version (see [`NwRelease::resolve_offline`])
+    pub fn is_unresolved(&self) -> bool {
+        !is_exact_version(&self.version)
+    }
+
+    /// `true` if the version was resolved from a non-exact specification
+    pub fn is_resolved(&self) -> bool {
+        self.spec.trim_start_matches('v') != self.version
+    }
+}
+
+/// Returns `true` if the version string is a complete `x.y.z` version
+pub fn is_exact_version(spec: &str) -> bool {
+    Version::parse(spec.trim_start_matches('v')).is_ok()
+}
+
+fn is_url(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
@@ -0,0 +1,106 @@
+use crate::prelude::*;
+use async_std::path::Path;
+use std::fmt;
+
+/// `wasm-pack` output target (`--target`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WasmTarget {
+    #[default]
+    Web,
+    Bundler,
+    Nodejs,
+    NoModules,
+    Deno,
+}
+
+impl fmt::Display for WasmTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WasmTarget::Web => write!(f, "web"),
+            WasmTarget::Bundler => write!(f, "bundler"),
+            WasmTarget::Nodejs => write!(f, "nodejs"),
+            WasmTarget::NoModules => write!(f, "no-modules"),
+            WasmTarget::Deno => write!(f, "deno"),
+        }
+    }
+}
+
+/// `wasm-pack` build profile
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WasmProfile {
+    Dev,
+    #[default]
+    Release,
+    Profiling,
+}
+
+impl fmt::Display for WasmProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WasmProfile::Dev => write!(f, "dev"),
+            WasmProfile::Release => write!(f, "release"),
+            WasmProfile::Profiling => write!(f, "profiling"),
+        }
+    }
+}
+
+impl WasmProfile {
+    /// `wasm-pack build` flag selecting the profile
+    pub fn flag(&self) -> Option<&'static str> {
+        match self {
+            WasmProfile::Dev => Some("--dev"),
+            WasmProfile::Release => None,
+            WasmProfile::Profiling => Some("--profiling"),
+        }
+    }
+}
+
+/// Command line arguments specified either as a single
+/// space-separated string or as a list of arguments
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum BuildArgs {
+    String(String),
+    Argv(Vec<String>),
+}
+
+impl BuildArgs {
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            BuildArgs::String(args) => args.split_whitespace().map(String::from).collect(),
+            BuildArgs::Argv(argv) => argv.clone(),
+        }
+    }
+}
+
+/// Name of the cargo package located in `folder`, used as the
+/// default `wasm-pack` output name of crates located in a sub-folder.
+/// If `offline` is set, the name is read from `Cargo.toml` (`--plan`).
+pub async fn wasm_crate_name(folder: &Path, offline: bool) -> Result<String> {
+    let metadata = if offline {
+        CargoMetadata::load_offline(folder).await?
+    } else {
+        CargoMetadata::load(folder)?
+    };
+    Ok(metadata.package(None, folder)?.name.clone())
+}
+
+/// `wasm-opt` command line optimizing `file` in place. `level` is
+/// one of `0`-`4`, `s` or `z` (optionally prefixed with `O` or `-O`).
+pub fn wasm_opt_args(level: &str, file: &Path) -> Result<Vec<String>> {
+    let level = level.trim_start_matches('-').trim_start_matches('O');
+    if !matches!(level, "0" | "1" | "2" | "3" | "4" | "s" | "z") {
+        return Err(format!("invalid `wasm-opt` optimization level `{level}`").into());
+    }
+
+    let file = file.to_string_lossy().to_string();
+    Ok(vec![
+        "wasm-opt".to_string(),
+        format!("-O{level}"),
+        file.clone(),
+        "-o".to_string(),
+        file,
+    ])
+}
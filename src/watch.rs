@@ -0,0 +1,61 @@
+use crate::prelude::*;
+use async_std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Sizes and modification times of the watched files
+type Snapshot = Vec<(String, u64, u128)>;
+
+/// Set of files matching globs relative to a folder,
+/// polled for additions, removals and modifications
+#[derive(Debug)]
+pub struct WatchSet {
+    folder: PathBuf,
+    globs: Vec<String>,
+    snapshot: Snapshot,
+}
+
+impl WatchSet {
+    pub fn try_new(folder: &Path, globs: &[String]) -> Result<WatchSet> {
+        let mut set = WatchSet {
+            folder: folder.to_path_buf(),
+            globs: globs.to_vec(),
+            snapshot: Vec::new(),
+        };
+        set.reset()?;
+        Ok(set)
+    }
+
+    /// `true` if the files have changed since the last call
+    pub fn poll(&mut self) -> Result<bool> {
+        let snapshot = self.snapshot()?;
+        if snapshot == self.snapshot {
+            return Ok(false);
+        }
+        self.snapshot = snapshot;
+        Ok(true)
+    }
+
+    /// Discard changes made since the last call to `poll`
+    pub fn reset(&mut self) -> Result<()> {
+        self.snapshot = self.snapshot()?;
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Result<Snapshot> {
+        let (files, _) = collect(&self.folder, &self.globs)?;
+        let mut snapshot = Vec::with_capacity(files.len());
+        for relative in files {
+            // files removed while scanning are omitted
+            let Ok(metadata) = std::fs::metadata(self.folder.join(&relative)) else {
+                continue;
+            };
+            let modified = metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_nanos())
+                .unwrap_or_default();
+            snapshot.push((relative, metadata.len(), modified));
+        }
+        Ok(snapshot)
+    }
+}
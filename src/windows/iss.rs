@@ -113,11 +113,11 @@ impl ISS {
 
     pub fn check_innosetup_compiler(&self) -> Result<()> {
         if !std::path::Path::new(INNO_SETUP_COMPIL32).exists() {
-            println!();
-            println!("fatal: unable to locate: {INNO_SETUP_COMPIL32}");
-            println!("please download innosetup 6 at:");
-            println!("https://jrsoftware.org/isdl.php");
-            println!();
+            log_warn!("InnoSetup", "unable to locate: `{INNO_SETUP_COMPIL32}`");
+            log_warn!(
+                "InnoSetup",
+                "please download innosetup 6 at: https://jrsoftware.org/isdl.php"
+            );
             return Err("missing InnoSetup compiler".into());
         }
         Ok(())
@@ -319,6 +319,7 @@ impl ISS {
         log_info!("InnoSetup", "building...");
         cmd!(INNO_SETUP_COMPIL32, "/cc", iss_file)
             .stdin_null()
+            .stdout_to_log()
             .run()?;
         let setup_size = std::fs::metadata(&self.output_file)?.len() as f64;
         log_info!(
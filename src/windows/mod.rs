@@ -11,6 +11,7 @@ use fs_extra::dir;
 use image::imageops::FilterType;
 use image::GenericImageView;
 use std::collections::HashMap;
+#[cfg(target_os = "windows")]
 use winres_edit::*;
 
 pub struct Windows {
@@ -76,28 +77,51 @@ impl Installer for Windows {
         if targets.contains(&Target::InnoSetup)
             && !std::path::Path::new(iss::INNO_SETUP_COMPIL32).exists()
         {
-            println!();
-            println!("fatal: unable to locate: `{}`", iss::INNO_SETUP_COMPIL32);
-            println!("please download innosetup 6 at:");
-            println!("https://jrsoftware.org/isdl.php");
-            println!();
+            log_warn!(
+                "InnoSetup",
+                "unable to locate: `{}`",
+                iss::INNO_SETUP_COMPIL32
+            );
+            log_warn!(
+                "InnoSetup",
+                "please download innosetup 6 at: https://jrsoftware.org/isdl.php"
+            );
             return Err("missing InnoSetup compiler".into());
         }
 
         Ok(())
     }
 
-    async fn create(&self, targets: &TargetSet) -> Result<Vec<PathBuf>> {
+    async fn create(&self, targets: &TargetSet) -> Result<Vec<Artifact>> {
         self.copy_nwjs_folder().await?;
         self.copy_app_data().await?;
-        self.update_resources().await?;
+        let update_resources = self
+            .ctx
+            .manifest
+            .windows
+            .as_ref()
+            .and_then(|windows| windows.update_resources)
+            .unwrap_or(true);
+        if update_resources {
+            self.update_resources().await?;
+        } else {
+            log_info!("Windows", "retaining default NW resources");
+        }
 
         // let tpl = create_installer_tpl(
         //     &self.ctx,
         //     &self.nwjs_root_folder
         // );
 
-        execute_actions(Stage::Package, &self.ctx, &self.tpl, &self.target_folder).await?;
+        execute_actions(
+            Stage::Package,
+            &self.ctx,
+            &self.tpl,
+            &[],
+            targets,
+            &self.target_folder,
+        )
+        .await?;
 
         let mut files = Vec::new();
 
@@ -116,7 +140,7 @@ impl Installer for Windows {
             let target_file = self.ctx.output_folder.join(filename);
             compress_folder(&self.target_folder, &target_file, level)?;
 
-            files.push(target_file);
+            files.push(Artifact::new(Target::Archive, target_file));
             // files.push(filename);
         }
 
@@ -133,7 +157,7 @@ impl Installer for Windows {
             );
 
             let filename = setup_script.create().await?;
-            files.push(filename);
+            files.push(Artifact::new(Target::InnoSetup, filename));
         }
 
         Ok(files)
@@ -146,6 +170,34 @@ impl Installer for Windows {
     fn target_folder(&self) -> PathBuf {
         self.target_folder.clone()
     }
+
+    fn executable(&self) -> PathBuf {
+        self.target_folder.join(&self.app_exe_file)
+    }
+
+    fn artifacts(&self, targets: &TargetSet) -> Vec<Artifact> {
+        let mut files = Vec::new();
+        if targets.contains(&Target::Archive) {
+            let filename = format!("{}.zip", self.ctx.app_snake_name);
+            files.push(Artifact::new(
+                Target::Archive,
+                self.ctx.output_folder.join(filename),
+            ));
+        }
+        #[cfg(any(target_os = "windows", feature = "multiplatform"))]
+        if targets.contains(&Target::InnoSetup) {
+            let application = &self.ctx.manifest.application;
+            let filename = format!(
+                "{}-{}-{}-{}.exe",
+                application.name, application.version, self.ctx.platform, self.ctx.arch
+            );
+            files.push(Artifact::new(
+                Target::InnoSetup,
+                self.ctx.output_folder.join(filename),
+            ));
+        }
+        files
+    }
 }
 
 impl Windows {
@@ -155,6 +207,7 @@ impl Windows {
         options.skip_exist = true;
 
         log_info!("Integrating", "NW binaries");
+        fs::create_dir_all(&self.target_folder).await?;
 
         dir::copy(
             Path::new(&self.ctx.deps.nwjs.target()),
@@ -192,6 +245,7 @@ impl Windows {
         )
         .await?;
 
+        self.ctx.copy_flavor_overlay(&self.target_folder).await?;
         self.ctx.update_package_json(&self.target_folder).await?;
 
         Ok(())
@@ -394,16 +448,13 @@ impl Windows {
             .unwrap_or_else(|err| panic!("Unable to open '{app_icon_png:?}': {err}"));
         let dimensions = src.dimensions();
         if dimensions.0 != 1024 || dimensions.1 != 1024 {
-            println!();
-            println!(
-                "WARNING: {}",
-                app_icon_png.file_name().unwrap().to_str().unwrap()
-            );
-            println!(
-                "         ^^^ icon dimensions are {}x{}; must be 1024x1024",
-                dimensions.0, dimensions.1
+            log_warn!("Resources", "`{}`", app_icon_png.display());
+            log_warn!(
+                "Resources",
+                "icon dimensions are {}x{}; must be 1024x1024",
+                dimensions.0,
+                dimensions.1
             );
-            println!();
         }
 
         cfg_if! {
@@ -498,9 +549,29 @@ impl Windows {
             app_icon_image_data.height(),
             app_icon_image_data.as_raw().clone(),
         );
-        let app_icon_encoded = ico::IconDirEntry::encode(&app_icon_image_ico).unwrap();
         // let app_res_file = self.ctx.build_folder.join(&self.app_exe_file);
         let app_res_file = self.target_folder.join(&self.app_exe_file);
+
+        cfg_if! {
+            if #[cfg(not(target_os = "windows"))] {
+                self.update_resources_with_rcedit(&app_res_file, &app_icon_image_ico, &version, &strings)
+                    .await
+            } else {
+                self.update_resources_with_winres(&app_res_file, &app_icon_image_ico, &version, &strings)
+            }
+        }
+    }
+
+    /// Update executable resources using `winres-edit` (Windows hosts)
+    #[cfg(target_os = "windows")]
+    fn update_resources_with_winres(
+        &self,
+        app_res_file: &Path,
+        app_icon_image_ico: &ico::IconImage,
+        version: &[u16; 4],
+        strings: &[(String, String)],
+    ) -> Result<()> {
+        let app_icon_encoded = ico::IconDirEntry::encode(app_icon_image_ico).unwrap();
         // let app_res_file = std::path::PathBuf::from(app_res_file.as_path());
         let mut resources = Resources::new(&std::path::PathBuf::from(app_res_file.as_path()));
         resources.load().unwrap_or_else(|err| {
@@ -525,8 +596,8 @@ impl Windows {
         resources
             .get_version_info()?
             .expect("Unable to get version info")
-            .set_file_version(&version)
-            .set_product_version(&version)
+            .set_file_version(version)
+            .set_product_version(version)
             .insert_strings(
                 &strings
                     .iter()
@@ -540,4 +611,64 @@ impl Windows {
 
         Ok(())
     }
+
+    /// Update executable resources using `rcedit` (non-Windows hosts,
+    /// where `rcedit` is typically a wrapper running `rcedit.exe` in Wine)
+    #[cfg(not(target_os = "windows"))]
+    async fn update_resources_with_rcedit(
+        &self,
+        app_res_file: &Path,
+        app_icon_image_ico: &ico::IconImage,
+        version: &[u16; 4],
+        strings: &[(String, String)],
+    ) -> Result<()> {
+        if cmd!("rcedit", "--help")
+            .stdout_null()
+            .stderr_null()
+            .unchecked()
+            .run()
+            .is_err()
+        {
+            return Err(format!(
+                "`rcedit` is required to update `{}` resources on this host; \
+                please install `rcedit` or set `update_resources = false` \
+                in the `[windows]` section of the manifest",
+                self.app_exe_file
+            )
+            .into());
+        }
+
+        let ico_file = self
+            .ctx
+            .cache_folder
+            .join(format!("{}.ico", self.ctx.manifest.application.name));
+        let mut icon_dir = ico::IconDir::new(ico::ResourceType::Icon);
+        icon_dir.add_entry(ico::IconDirEntry::encode(app_icon_image_ico)?);
+        icon_dir.write(std::fs::File::create(&ico_file)?)?;
+
+        let version = version
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(".");
+        let mut argv = vec![
+            app_res_file.to_string_lossy().to_string(),
+            "--set-icon".to_string(),
+            ico_file.to_string_lossy().to_string(),
+            "--set-file-version".to_string(),
+            version.clone(),
+            "--set-product-version".to_string(),
+            version,
+        ];
+        for (name, value) in strings {
+            argv.extend([
+                "--set-version-string".to_string(),
+                name.clone(),
+                value.clone(),
+            ]);
+        }
+        cmd("rcedit", argv).stdout_to_log().run()?;
+
+        Ok(())
+    }
 }